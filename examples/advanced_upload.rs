@@ -1,7 +1,6 @@
 use clap::Parser;
 use imgbb::{ImgBB, Error};
 use std::time::Duration;
-use tokio;
 
 #[derive(Parser)]
 #[clap(author, version, about = "Advanced ImgBB upload example")]
@@ -42,7 +41,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create ImgBB client with custom settings
     let imgbb = ImgBB::builder(cli.key)
         .timeout(Duration::from_secs(cli.timeout))
-        .user_agent(format!("MyApp/1.0 ImgBB-Uploader"))
+        .user_agent("MyApp/1.0 ImgBB-Uploader")
         .build()?;
     
     println!("Loading file: {}", cli.path);
@@ -74,13 +73,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Upload with detailed error handling
     println!("Uploading image (timeout: {} seconds)...", cli.timeout);
-    let res = match builder.upload().await {
-        Ok(res) => res,
-        Err(Error::ReqwestError(e)) if e.is_timeout() => {
+    let image = match builder.upload().await {
+        Ok(image) => image,
+        Err(Error::Timeout) => {
             eprintln!("✗ Upload timed out after {} seconds", cli.timeout);
-            return Err(e.into());
+            return Err(Error::Timeout.into());
         },
-        Err(Error::ReqwestError(e)) if e.is_connect() => {
+        Err(Error::Connection(e)) => {
             eprintln!("✗ Connection error: {}", e);
             return Err(e.into());
         },
@@ -90,40 +89,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
     
-    // Check the response
-    if let Some(true) = res.success {
-        if let Some(data) = res.data {
-            println!("\n✓ Upload successful!");
-            println!("----------------------------------");
-            println!("Image ID: {}", data.id.unwrap_or_default());
-            println!("Image URL: {}", data.url.unwrap_or_default());
-            println!("Display URL: {}", data.display_url.unwrap_or_default());
-            println!("Delete URL: {}", data.delete_url.unwrap_or_default());
-            
-            if let Some(width) = data.width {
-                if let Some(height) = data.height {
-                    println!("Dimensions: {}x{}", width, height);
-                }
-            }
-            
-            if let Some(size) = data.size {
-                println!("Size: {} bytes", size);
-            }
-            
-            if let Some(exp) = data.expiration {
-                println!("Expires in: {} seconds", exp);
-            }
-            
-            println!("----------------------------------");
-        }
-    } else if let Some(error) = res.error {
-        eprintln!("✗ Upload failed: {}", error.message.unwrap_or_default());
-        if let Some(code) = error.code {
-            eprintln!("Error code: {}", code);
-        }
-    } else {
-        eprintln!("✗ Upload failed with unknown error");
+    println!("\n✓ Upload successful!");
+    println!("----------------------------------");
+    println!("Image ID: {}", image.id);
+    println!("Image URL: {}", image.url);
+    println!("Display URL: {}", image.display_url);
+    println!("Delete URL: {}", image.delete_url);
+    println!("Dimensions: {}x{}", image.width, image.height);
+    println!("Size: {} bytes", image.size);
+    
+    if let Some(exp) = image.expiration {
+        println!("Expires in: {} seconds", exp);
     }
     
+    println!("----------------------------------");
+    
     Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,29 @@
+use clap::Parser;
+use imgbb::blocking::ImgBB;
+use imgbb::Error;
+
+#[derive(Parser)]
+#[command(about = "Upload an image with the blocking ImgBB client")]
+struct Cli {
+    /// ImgBB API key
+    #[arg(short, long)]
+    key: String,
+
+    /// Path to the image file
+    #[arg(short, long)]
+    file: String,
+}
+
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+
+    // No async runtime is needed for the blocking client
+    let imgbb = ImgBB::new(cli.key);
+
+    let image = imgbb.upload_file(&cli.file)?;
+
+    println!("Image URL: {}", image.url);
+    println!("Delete URL: {}", image.delete_url);
+
+    Ok(())
+}
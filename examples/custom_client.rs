@@ -32,8 +32,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build()?;
 
     let imgbb = ImgBB::new_with_client(&args.key, client);
-    let response = imgbb.upload_file(&args.file).await?;
-    println!("Upload successful: {}", response.data.unwrap().url.unwrap());
+    let image = imgbb.upload_file(&args.file).await?;
+    println!("Upload successful: {}", image.url);
 
     // Example 2: Using builder pattern
     println!("\nExample 2: Using builder pattern");
@@ -47,8 +47,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build()
         .expect("Failed to create ImgBB client");
     
-    let response = imgbb.upload_file(&args.file).await?;
-    println!("Upload successful: {}", response.data.unwrap().url.unwrap());
+    let image = imgbb.upload_file(&args.file).await?;
+    println!("Upload successful: {}", image.url);
 
     // Example 3: With proxy (if provided)
     if let Some(proxy_url) = args.proxy {
@@ -62,8 +62,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .build()?;
 
         let imgbb = ImgBB::new_with_client(&args.key, client);
-        let response = imgbb.upload_file(&args.file).await?;
-        println!("Upload successful: {}", response.data.unwrap().url.unwrap());
+        let image = imgbb.upload_file(&args.file).await?;
+        println!("Upload successful: {}", image.url);
     }
 
     Ok(())
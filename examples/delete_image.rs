@@ -1,6 +1,5 @@
 use clap::Parser;
 use imgbb::{Error, ImgBB};
-use tokio;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -29,16 +28,19 @@ async fn main() -> Result<(), Error> {
             println!("✅ Image successfully deleted!");
             Ok(())
         },
-        Err(Error::InvalidApiKey) => {
+        Err(err @ Error::InvalidApiKey { .. }) => {
             eprintln!("❌ Invalid API key! Please check your API key and try again.");
-            Err(Error::InvalidApiKey)
+            Err(err)
         },
-        Err(Error::ApiError { message, status, code }) => {
+        Err(Error::ApiError { message, status, code, context }) => {
             eprintln!("❌ API Error (Status: {}, Code: {:?}): {}", 
                       status.unwrap_or(0), 
                       code, 
                       message);
-            Err(Error::ApiError { message, status, code })
+            if let Some(context) = &context {
+                eprintln!("   Response body: {}", context.body);
+            }
+            Err(Error::ApiError { message, status, code, context })
         },
         Err(err) => {
             eprintln!("❌ Delete failed: {}", err);
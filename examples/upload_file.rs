@@ -1,7 +1,6 @@
 use clap::Parser;
 use imgbb::{Error, ImgBB};
 use std::time::Duration;
-use tokio;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -71,34 +70,24 @@ async fn main() -> Result<(), Error> {
         }
         
         // Upload the image
-        let response = builder.upload().await?;
+        let image = builder.upload().await?;
         
         // Print the result
-        if let Some(data) = response.data {
-            println!("✅ Upload successful!");
-            println!("ID: {}", data.id.unwrap_or_default());
-            println!("URL: {}", data.url.unwrap_or_default());
-            println!("Delete URL: {}", data.delete_url.unwrap_or_default());
-            
-            if let (Some(width), Some(height)) = (data.width, data.height) {
-                println!("Dimensions: {}x{}", width, height);
-            }
-            
-            if let Some(size) = data.size {
-                println!("Size: {} bytes", size);
-            }
-        }
+        println!("✅ Upload successful!");
+        println!("ID: {}", image.id);
+        println!("URL: {}", image.url);
+        println!("Delete URL: {}", image.delete_url);
+        println!("Dimensions: {}x{}", image.width, image.height);
+        println!("Size: {} bytes", image.size);
     } else {
         println!("Using simple upload...");
         // Simple upload
-        let response = imgbb.upload_file(&cli.file).await?;
+        let image = imgbb.upload_file(&cli.file).await?;
         
         // Print the result
-        if let Some(data) = response.data {
-            println!("✅ Upload successful!");
-            println!("URL: {}", data.url.unwrap_or_default());
-            println!("Delete URL: {}", data.delete_url.unwrap_or_default());
-        }
+        println!("✅ Upload successful!");
+        println!("URL: {}", image.url);
+        println!("Delete URL: {}", image.delete_url);
     }
     
     Ok(())
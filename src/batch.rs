@@ -0,0 +1,170 @@
+use std::path::PathBuf;
+
+use crate::{Error, Expiration, UploaderBuilder};
+
+/// Description of a single upload in a batch
+///
+/// The image source is only read when the upload starts, so a batch of
+/// files never holds more images in memory than its concurrency limit.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::{ImgBB, UploadSpec};
+///
+/// async fn example() {
+///     let imgbb = ImgBB::new("your_api_key");
+///
+///     let specs = vec![
+///         UploadSpec::file("path/to/first.jpg").title("First"),
+///         UploadSpec::url("https://example.com/second.png").expiration(3600),
+///     ];
+///
+///     for result in imgbb.upload_batch(specs, 4).await {
+///         match result {
+///             Ok(image) => println!("Uploaded: {}", image.url),
+///             Err(e) => eprintln!("Upload failed: {}", e),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct UploadSpec {
+    source: SpecSource,
+    name: Option<String>,
+    title: Option<String>,
+    album: Option<String>,
+    expiration: Option<Expiration>,
+}
+
+#[derive(Clone)]
+enum SpecSource {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+    Base64(String),
+    Url(String),
+    Builder(Box<UploaderBuilder>),
+}
+
+impl UploadSpec {
+    fn new(source: SpecSource) -> Self {
+        Self {
+            source,
+            name: None,
+            title: None,
+            album: None,
+            expiration: None,
+        }
+    }
+
+    /// Upload the file at the given path
+    pub fn file<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self::new(SpecSource::File(path.into()))
+    }
+
+    /// Upload raw image bytes
+    pub fn bytes<T>(data: T) -> Self
+    where
+        T: Into<Vec<u8>>,
+    {
+        Self::new(SpecSource::Bytes(data.into()))
+    }
+
+    /// Upload base64 encoded image data
+    pub fn base64<T>(data: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new(SpecSource::Base64(data.into()))
+    }
+
+    /// Upload an image from a remote http(s) URL
+    pub fn url<T>(url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new(SpecSource::Url(url.into()))
+    }
+
+    /// Set the image name
+    pub fn name<T>(mut self, name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the image title
+    pub fn title<T>(mut self, title: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the album ID
+    pub fn album<T>(mut self, album: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.album = Some(album.into());
+        self
+    }
+
+    /// Set the expiration time, in seconds or as a [Duration](std::time::Duration)
+    pub fn expiration<T>(mut self, expiration: T) -> Self
+    where
+        T: Into<Expiration>,
+    {
+        self.expiration = Some(expiration.into());
+        self
+    }
+
+    /// Apply the spec to a fresh uploader builder
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or the URL is invalid
+    pub(crate) fn into_builder(self, builder: UploaderBuilder) -> Result<UploaderBuilder, Error> {
+        let mut builder = match self.source {
+            SpecSource::File(path) => builder.file(path)?,
+            SpecSource::Bytes(data) => builder.bytes(data),
+            SpecSource::Base64(data) => builder.data(data),
+            SpecSource::Url(url) => builder.url(url)?,
+            SpecSource::Builder(builder) => *builder,
+        };
+
+        if let Some(name) = self.name {
+            builder = builder.name(name);
+        }
+
+        if let Some(title) = self.title {
+            builder = builder.title(title);
+        }
+
+        if let Some(album) = self.album {
+            builder = builder.album(album);
+        }
+
+        if let Some(expiration) = self.expiration {
+            builder = builder.expiration(expiration);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Use a fully configured uploader builder as a batch item
+///
+/// This gives access to every builder option, such as the transport or a
+/// streamed source.
+impl From<UploaderBuilder> for UploadSpec {
+    fn from(builder: UploaderBuilder) -> Self {
+        Self::new(SpecSource::Builder(Box::new(builder)))
+    }
+}
@@ -0,0 +1,706 @@
+//! Blocking (synchronous) ImgBB client
+//!
+//! The types in this module mirror the async [ImgBB](crate::ImgBB) client,
+//! but are backed by [reqwest::blocking::Client] so they can be used
+//! without an async runtime.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use imgbb::blocking::ImgBB;
+//!
+//! fn example() -> Result<(), imgbb::Error> {
+//!     let imgbb = ImgBB::new("your_api_key");
+//!
+//!     let image = imgbb.upload_file("path/to/image.jpg")?;
+//!
+//!     println!("Image URL: {}", image.url);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use bytes::Bytes;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::dedupe::{self, DedupeCache};
+use crate::ledger::{self, EntryStatus, LedgerEntry, UploadLedger};
+use crate::model::{Response, UploadedImage};
+use crate::rate_limit::RateLimiter;
+use crate::retry::{self, RetryPolicy};
+use crate::sweep::{self, Step, SweepPolicy, SweepReport};
+use crate::transport::{self, Transport};
+#[cfg(feature = "image-processing")]
+use crate::processing::{self, FitPolicy, Transformation};
+use crate::{
+    parse_check_response, parse_delete_response, parse_upload_response, upload_endpoint, Error, Expiration,
+    ImageFormat, ImageSource, ProcessedUpload, UploadOptions, APP_USER_AGENT, DEFAULT_BASE_URL, DEFAULT_MAX_IMAGE_SIZE,
+};
+
+/// Blocking client for interacting with the ImgBB API
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug)]
+pub struct ImgBB {
+    client: reqwest::blocking::Client,
+    api_key: String,
+    base_url: String,
+    retry: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    max_image_size: u64,
+    dedupe: Option<Arc<dyn DedupeCache>>,
+    ledger: Option<Arc<dyn UploadLedger>>,
+}
+
+/// Builder for creating a customized blocking ImgBB client
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::blocking::ImgBB;
+/// use std::time::Duration;
+///
+/// let imgbb = ImgBB::builder("your_api_key")
+///     .timeout(Duration::from_secs(30))
+///     .user_agent("MyApp/1.0")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ImgBBBuilder {
+    api_key: String,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    client: Option<reqwest::blocking::Client>,
+    base_url: Option<String>,
+    retry: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    max_image_size: Option<u64>,
+    dedupe: Option<Arc<dyn DedupeCache>>,
+    ledger: Option<Arc<dyn UploadLedger>>,
+}
+
+impl ImgBB {
+    /// Creates a new blocking ImgBB client with the given API key
+    pub fn new<T>(api_key: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            client: reqwest::blocking::Client::builder()
+                .user_agent(APP_USER_AGENT)
+                .build()
+                .unwrap(),
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retry: RetryPolicy::disabled(),
+            rate_limiter: None,
+            max_image_size: DEFAULT_MAX_IMAGE_SIZE,
+            dedupe: None,
+            ledger: None,
+        }
+    }
+
+    /// Creates a new builder for a customized blocking ImgBB client
+    pub fn builder<T>(api_key: T) -> ImgBBBuilder
+    where
+        T: Into<String>,
+    {
+        ImgBBBuilder {
+            api_key: api_key.into(),
+            timeout: None,
+            user_agent: None,
+            client: None,
+            base_url: None,
+            retry: None,
+            rate_limiter: None,
+            max_image_size: None,
+            dedupe: None,
+            ledger: None,
+        }
+    }
+
+    /// Creates a new blocking ImgBB client with the given API key and reqwest client
+    pub fn new_with_client<T>(api_key: T, client: reqwest::blocking::Client) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            client,
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retry: RetryPolicy::disabled(),
+            rate_limiter: None,
+            max_image_size: DEFAULT_MAX_IMAGE_SIZE,
+            dedupe: None,
+            ledger: None,
+        }
+    }
+
+    /// Create a new uploader with custom options
+    pub fn upload_builder(&self) -> UploaderBuilder {
+        UploaderBuilder {
+            api_key: self.api_key.clone(),
+            options: UploadOptions::new(self.max_image_size),
+            base_url: self.base_url.clone(),
+            retry: self.retry.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            dedupe: self.dedupe.clone(),
+            ledger: self.ledger.clone(),
+            client: self.client.clone(),
+        }
+    }
+
+    /// Delete an image from ImgBB using the given delete URL
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The API request fails
+    /// - The API returns an error response
+    /// - The API key is invalid
+    pub fn delete<T>(&self, delete_url: T) -> Result<(), Error>
+    where
+        T: Into<String>,
+    {
+        let query = [("key", self.api_key.as_str())];
+        let delete_url = delete_url.into();
+
+        retry::execute_blocking(
+            &self.retry,
+            self.rate_limiter.as_ref(),
+            || Ok(self.client.delete(&delete_url).query(&query)),
+            parse_delete_response,
+        )
+    }
+
+    /// Ledger the client records its uploads in, if one was set with
+    /// [ImgBBBuilder::ledger]
+    pub fn ledger(&self) -> Option<&dyn UploadLedger> {
+        self.ledger.as_deref()
+    }
+
+    /// Delete recorded images from ImgBB and remove them from the ledger
+    ///
+    /// See [crate::ImgBB::delete_recorded].
+    pub fn delete_recorded<I>(&self, entries: I) -> Vec<Result<(), Error>>
+    where
+        I: IntoIterator<Item = LedgerEntry>,
+    {
+        entries
+            .into_iter()
+            .map(|entry| {
+                self.delete(ledger::delete_url(&entry)?)?;
+                let forgotten = ledger::forget(self.ledger(), &entry);
+                dedupe::evict(self.dedupe.as_deref(), entry.hash.as_ref());
+                forgotten
+            })
+            .collect()
+    }
+
+    /// Reconcile the client's ledger with the images on ImgBB
+    ///
+    /// See [crate::ImgBB::sweep].
+    pub fn sweep(&self, policy: &SweepPolicy) -> Result<SweepReport, Error> {
+        let ledger = sweep::require_ledger(self.ledger())?;
+        let now = std::time::SystemTime::now();
+        let mut report = SweepReport::default();
+
+        for entry in ledger.entries()? {
+            match sweep::plan(&entry, policy, now) {
+                Step::Skip => {}
+                Step::MarkExpired => report.mark(ledger, entry, EntryStatus::Expired),
+                Step::Delete => {
+                    let result = ledger::delete_url(&entry).and_then(|delete_url| self.delete(delete_url));
+                    report.deleted(self.dedupe.as_deref(), entry, result);
+                }
+                Step::Check => {
+                    let result = sweep::check_url(&entry).and_then(|url| {
+                        retry::execute_blocking(
+                            &self.retry,
+                            self.rate_limiter.as_ref(),
+                            || Ok(self.client.head(url)),
+                            parse_check_response,
+                        )
+                    });
+                    report.checked(ledger, self.dedupe.as_deref(), entry, result);
+                }
+            }
+        }
+
+        report.forget_deleted(ledger);
+        Ok(report)
+    }
+
+    /// Straightforward upload base64 data to ImgBB
+    pub fn upload_base64<T>(&self, data: T) -> Result<UploadedImage, Error>
+    where
+        T: AsRef<str>,
+    {
+        self.upload_builder().data(data).upload()
+    }
+
+    /// Straightforward upload bytes data to ImgBB
+    pub fn upload_bytes<T>(&self, data: T) -> Result<UploadedImage, Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.upload_builder().bytes(data).upload()
+    }
+
+    /// Straightforward upload file to ImgBB
+    pub fn upload_file<P>(&self, path: P) -> Result<UploadedImage, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.upload_builder().file(path)?.upload()
+    }
+
+    /// Straightforward upload an image from a remote http(s) URL to ImgBB
+    pub fn upload_url<T>(&self, url: T) -> Result<UploadedImage, Error>
+    where
+        T: AsRef<str>,
+    {
+        self.upload_builder().url(url)?.upload()
+    }
+
+    /// Upload base64 data to ImgBB with expiration time (seconds)
+    pub fn upload_base64_with_expiration<T>(
+        &self,
+        data: T,
+        expiration: u64,
+    ) -> Result<UploadedImage, Error>
+    where
+        T: AsRef<str>,
+    {
+        self.upload_builder().data(data).expiration(expiration).upload()
+    }
+
+    /// Upload bytes data to ImgBB with expiration time (seconds)
+    pub fn upload_bytes_with_expiration<T>(
+        &self,
+        data: T,
+        expiration: u64,
+    ) -> Result<UploadedImage, Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.upload_builder().bytes(data).expiration(expiration).upload()
+    }
+
+    /// Upload file to ImgBB with expiration time (seconds)
+    pub fn upload_file_with_expiration<P>(
+        &self,
+        path: P,
+        expiration: u64,
+    ) -> Result<UploadedImage, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.upload_builder().file(path)?.expiration(expiration).upload()
+    }
+}
+
+impl ImgBBBuilder {
+    /// Set a custom timeout for all requests
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set a custom user agent
+    pub fn user_agent<T>(mut self, user_agent: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Set a custom blocking reqwest client
+    ///
+    /// Note: If you provide a custom client, any timeout or user agent
+    /// settings specified on the builder will be ignored in favor of
+    /// the custom client's configuration.
+    pub fn client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Set a custom base URL for the ImgBB API
+    ///
+    /// See [crate::ImgBBBuilder::base_url].
+    pub fn base_url<T>(mut self, base_url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Set the retry policy for uploads and deletes
+    ///
+    /// See [crate::ImgBBBuilder::retry].
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Set a rate limiter shared by all requests of the client
+    ///
+    /// See [crate::ImgBBBuilder::rate_limiter].
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Set the largest raw image size, in bytes, that uploads accept
+    ///
+    /// See [crate::ImgBBBuilder::max_image_size].
+    pub fn max_image_size(mut self, max_image_size: u64) -> Self {
+        self.max_image_size = Some(max_image_size);
+        self
+    }
+
+    /// Skip uploads of images whose bytes were uploaded before
+    ///
+    /// See [crate::ImgBBBuilder::dedupe_cache].
+    pub fn dedupe_cache<C>(mut self, cache: C) -> Self
+    where
+        C: DedupeCache + 'static,
+    {
+        self.dedupe = Some(Arc::new(cache));
+        self
+    }
+
+    /// Record every successful upload in a ledger
+    ///
+    /// See [crate::ImgBBBuilder::ledger].
+    pub fn ledger<L>(mut self, ledger: L) -> Self
+    where
+        L: UploadLedger + 'static,
+    {
+        self.ledger = Some(Arc::new(ledger));
+        self
+    }
+
+    /// Build the blocking ImgBB client
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reqwest client builder fails to build.
+    pub fn build(self) -> Result<ImgBB, Error> {
+        let base_url = self
+            .base_url
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        let retry = self.retry.unwrap_or_else(RetryPolicy::disabled);
+        let max_image_size = self.max_image_size.unwrap_or(DEFAULT_MAX_IMAGE_SIZE);
+
+        // If a custom client was provided, use it
+        if let Some(client) = self.client {
+            return Ok(ImgBB {
+                client,
+                api_key: self.api_key,
+                base_url,
+                retry,
+                rate_limiter: self.rate_limiter,
+                max_image_size,
+                dedupe: self.dedupe,
+                ledger: self.ledger,
+            });
+        }
+
+        let mut client_builder = reqwest::blocking::Client::builder().user_agent(
+            self.user_agent.unwrap_or_else(|| APP_USER_AGENT.to_string()),
+        );
+
+        if let Some(timeout) = self.timeout {
+            client_builder = client_builder.timeout(timeout);
+        }
+
+        let client = client_builder.build().map_err(Error::from)?;
+
+        Ok(ImgBB {
+            client,
+            api_key: self.api_key,
+            base_url,
+            retry,
+            rate_limiter: self.rate_limiter,
+            max_image_size,
+            dedupe: self.dedupe,
+            ledger: self.ledger,
+        })
+    }
+}
+
+/// A blocking builder for creating an uploader with more options
+///
+/// This is the synchronous counterpart of [crate::UploaderBuilder].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::blocking::ImgBB;
+///
+/// fn example() -> Result<(), imgbb::Error> {
+///     let imgbb = ImgBB::new("your_api_key");
+///
+///     let image = imgbb.upload_builder()
+///         .file("path/to/image.jpg")?
+///         .name("custom_name")
+///         .title("My Image")
+///         .expiration(86400) // 24 hours
+///         .upload()?;
+///
+///     println!("Upload successful: {}", image.url);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct UploaderBuilder {
+    api_key: String,
+    options: UploadOptions,
+    base_url: String,
+    retry: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    dedupe: Option<Arc<dyn DedupeCache>>,
+    ledger: Option<Arc<dyn UploadLedger>>,
+    client: reqwest::blocking::Client,
+}
+
+impl UploaderBuilder {
+    /// Set the base64 data for upload
+    pub fn data<T>(mut self, data: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        self.options.set_data(ImageSource::Base64(data.as_ref().to_owned()));
+        self
+    }
+
+    /// Set the raw bytes data for upload
+    pub fn bytes<T>(mut self, data: T) -> Self
+    where
+        T: AsRef<[u8]>,
+    {
+        self.options.set_data(ImageSource::Bytes {
+            data: Bytes::copy_from_slice(data.as_ref()),
+            filename: None,
+        });
+        self
+    }
+
+    /// Set data from a file path
+    ///
+    /// See [crate::UploaderBuilder::file].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read
+    pub fn file<P>(mut self, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.options.set_file(path.as_ref())?;
+        Ok(self)
+    }
+
+    /// Set a remote image URL for upload
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::InvalidParameters](Error::InvalidParameters) if the URL
+    /// cannot be parsed or does not use the http or https scheme
+    pub fn url<T>(mut self, url: T) -> Result<Self, Error>
+    where
+        T: AsRef<str>,
+    {
+        self.options.set_data(ImageSource::url(url.as_ref())?);
+        Ok(self)
+    }
+
+    /// Set the expiration time, in seconds or as a [Duration]
+    ///
+    /// The expiration is checked against the range accepted by ImgBB when
+    /// the upload is sent, see [Expiration].
+    pub fn expiration<T>(mut self, expiration: T) -> Self
+    where
+        T: Into<Expiration>,
+    {
+        self.options.expiration = Some(expiration.into());
+        self
+    }
+
+    /// Set the image name
+    pub fn name<T>(mut self, name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// Set the image title
+    pub fn title<T>(mut self, title: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.options.title = Some(title.into());
+        self
+    }
+
+    /// Set the album ID
+    pub fn album<T>(mut self, album: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.options.album = Some(album.into());
+        self
+    }
+
+    /// Set how the image data is sent
+    ///
+    /// See [crate::UploaderBuilder::transport].
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.options.transport = transport;
+        self
+    }
+
+    /// Shrink the image before uploading so it fits the given limits
+    ///
+    /// See [crate::UploaderBuilder::fit_within].
+    #[cfg(feature = "image-processing")]
+    pub fn fit_within(mut self, max_bytes: u64, max_dimension: u32) -> Self {
+        self.options.fit = Some(FitPolicy {
+            max_bytes,
+            max_dimension,
+        });
+        self
+    }
+
+    /// Remove metadata from the image before uploading
+    ///
+    /// See [crate::UploaderBuilder::strip_metadata].
+    pub fn strip_metadata(mut self, strip: bool) -> Self {
+        self.options.strip_metadata = strip;
+        self
+    }
+
+    /// Also remove ICC color profiles when stripping metadata
+    ///
+    /// See [crate::UploaderBuilder::strip_icc_profile].
+    pub fn strip_icc_profile(mut self, strip: bool) -> Self {
+        self.options.strip_options = self.options.strip_options.remove_icc(strip);
+        self
+    }
+
+    /// Remove metadata and shrink the image as requested, then upload it
+    ///
+    /// See [crate::UploaderBuilder::upload_processed].
+    pub fn upload_processed(mut self) -> Result<ProcessedUpload, Error> {
+        self.options.check_before_processing()?;
+        let metadata = self.options.apply_strip()?;
+        #[cfg(feature = "image-processing")]
+        let transformation = self.process()?;
+        let image = self.upload()?;
+
+        Ok(ProcessedUpload {
+            image,
+            metadata,
+            #[cfg(feature = "image-processing")]
+            transformation,
+        })
+    }
+
+    /// Apply the fit policy, reporting a panic while decoding or encoding
+    /// the image as [Error::ProcessingTask] like the async client
+    #[cfg(feature = "image-processing")]
+    fn process(&mut self) -> Result<Option<Transformation>, Error> {
+        let options = &mut self.options;
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| options.apply_fit()))
+            .map_err(|payload| Error::ProcessingTask(processing::panic_message(&*payload)))?
+    }
+
+    /// Format of the image, detected from its leading bytes
+    ///
+    /// This is the MIME type used for multipart uploads. Returns `None` for
+    /// streams, URLs and images of unknown format.
+    pub fn format(&self) -> Option<ImageFormat> {
+        self.options.data.as_ref().and_then(ImageSource::format)
+    }
+
+    /// Upload the image with all specified options
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - No image data has been set
+    /// - The image is in a format ImgBB does not accept, as
+    ///   [Error::UnsupportedFormat](Error::UnsupportedFormat)
+    /// - The API request fails
+    /// - The API returns an error response
+    /// - The response lacks a required field, as
+    ///   [Error::MissingField](Error::MissingField)
+    pub fn upload(self) -> Result<UploadedImage, Error> {
+        self.send()?.try_into()
+    }
+
+    /// Upload the image and return the API response as-is
+    ///
+    /// Like [upload](UploaderBuilder::upload), but without checking that the
+    /// response contains every field of an [UploadedImage].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - No image data has been set
+    /// - The API request fails
+    /// - The API returns an error response
+    pub fn upload_response(self) -> Result<Response, Error> {
+        self.send()
+    }
+
+    /// Send the upload request, or return the cached response of an
+    /// identical upload
+    fn send(mut self) -> Result<Response, Error> {
+        self.options.check_before_processing()?;
+        self.options.apply_strip()?;
+        #[cfg(feature = "image-processing")]
+        self.process()?;
+
+        self.options.check_image()?;
+        let query = self.options.query(&self.api_key)?;
+
+        let hash = (self.dedupe.is_some() || self.ledger.is_some())
+            .then(|| self.options.content_hash())
+            .flatten();
+        let cached = self.dedupe.clone().zip(hash);
+        if let Some((cache, hash)) = &cached {
+            if let Some(response) = dedupe::lookup(cache.as_ref(), hash) {
+                return Ok(response);
+            }
+        }
+
+        let endpoint = upload_endpoint(&self.base_url);
+        let response = retry::execute_blocking(
+            &self.retry,
+            self.rate_limiter.as_ref(),
+            || {
+                let request = self.client.post(&endpoint).query(&query);
+                transport::apply_blocking(request, self.options.body()?)
+            },
+            parse_upload_response,
+        )?;
+
+        if let Some((cache, hash)) = cached {
+            dedupe::store(cache.as_ref(), hash, &response);
+        }
+        if let Some(ledger) = &self.ledger {
+            ledger::record(ledger.as_ref(), &response, self.options.provenance(hash))?;
+        }
+        Ok(response)
+    }
+}
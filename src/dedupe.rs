@@ -0,0 +1,488 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::model::Response;
+use crate::Error;
+
+/// SHA-256 hash of raw image bytes, used as the key of a [DedupeCache]
+///
+/// Displayed and serialized as lowercase hex.
+///
+/// # Examples
+///
+/// ```rust
+/// use imgbb::dedupe::ContentHash;
+///
+/// let hash = ContentHash::of(b"image");
+/// assert_eq!(hash.to_string().len(), 64);
+/// assert_eq!(hash.to_string().parse::<ContentHash>().unwrap(), hash);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContentHash([u8; 32]);
+
+impl ContentHash {
+    /// Hash raw image bytes
+    pub fn of(data: &[u8]) -> Self {
+        Self(Sha256::digest(data).into())
+    }
+
+    /// The hash as bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ContentHash {
+    type Err = Error;
+
+    fn from_str(hex: &str) -> Result<Self, Error> {
+        let invalid = || Error::invalid_parameters(format!("Invalid content hash '{}'", hex));
+
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for ContentHash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContentHash {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Store of upload responses keyed by the hash of the uploaded bytes
+///
+/// When a client has a cache, set with
+/// [ImgBBBuilder::dedupe_cache](crate::ImgBBBuilder::dedupe_cache), images
+/// whose bytes were uploaded before are not sent again: the stored
+/// [Response] is returned instead. Only the image bytes are hashed, so the
+/// name, title, album and expiration of later uploads are ignored on a hit.
+///
+/// Cached responses of expired images are removed when they are looked up,
+/// and [evict_expired](DedupeCache::evict_expired) removes all of them.
+/// Errors reading or writing the cache do not fail uploads: the image is
+/// uploaded as if it was not cached.
+///
+/// Implementations are provided for memory, a JSON file and, with the
+/// `sqlite` feature, an SQLite database.
+pub trait DedupeCache: fmt::Debug + Send + Sync {
+    /// Response stored for the hash
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be read
+    fn get(&self, hash: &ContentHash) -> Result<Option<Response>, Error>;
+
+    /// Store the response of an upload, replacing any previous one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be written
+    fn insert(&self, hash: ContentHash, response: &Response) -> Result<(), Error>;
+
+    /// Remove the response stored for the hash
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be written
+    fn remove(&self, hash: &ContentHash) -> Result<(), Error>;
+
+    /// Remove the responses of images that have expired at `now`
+    ///
+    /// Returns the number of removed responses.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be written
+    fn evict_expired(&self, now: SystemTime) -> Result<usize, Error>;
+}
+
+impl<T> DedupeCache for Arc<T>
+where
+    T: DedupeCache + ?Sized,
+{
+    fn get(&self, hash: &ContentHash) -> Result<Option<Response>, Error> {
+        (**self).get(hash)
+    }
+
+    fn insert(&self, hash: ContentHash, response: &Response) -> Result<(), Error> {
+        (**self).insert(hash, response)
+    }
+
+    fn remove(&self, hash: &ContentHash) -> Result<(), Error> {
+        (**self).remove(hash)
+    }
+
+    fn evict_expired(&self, now: SystemTime) -> Result<usize, Error> {
+        (**self).evict_expired(now)
+    }
+}
+
+/// Whether the image of a cached response has expired at `now`
+fn is_expired(response: &Response, now: SystemTime) -> bool {
+    response.data.as_ref().is_some_and(|data| data.is_expired(now))
+}
+
+/// Response cached for the hash, evicting it if the image has expired
+///
+/// Like [store], this is best effort: a cache that cannot be read is
+/// treated as a miss, and the image is uploaded.
+pub(crate) fn lookup(cache: &dyn DedupeCache, hash: &ContentHash) -> Option<Response> {
+    let response = cache.get(hash).ok()??;
+    if is_expired(&response, SystemTime::now()) {
+        let _ = cache.remove(hash);
+        return None;
+    }
+    Some(response)
+}
+
+/// Cache the response of a successful upload
+///
+/// Caching is best effort: the image is already on ImgBB, so a cache that
+/// cannot be written must not turn the upload into an error and lose its
+/// delete URL. The image is uploaded again next time instead.
+pub(crate) fn store(cache: &dyn DedupeCache, hash: ContentHash, response: &Response) {
+    if response.data.is_some() {
+        let _ = cache.insert(hash, response);
+    }
+}
+
+/// Remove the cached response of a deleted image, if the client has a cache
+/// and the hash of the image is known
+///
+/// Like [store], this is best effort: the image is already deleted, and a
+/// cached response that outlives it is only used until the image expires.
+pub(crate) fn evict(cache: Option<&dyn DedupeCache>, hash: Option<&ContentHash>) {
+    if let (Some(cache), Some(hash)) = (cache, hash) {
+        let _ = cache.remove(hash);
+    }
+}
+
+/// Remove the expired responses from a map of entries
+fn evict_expired_entries(entries: &mut BTreeMap<ContentHash, Response>, now: SystemTime) -> usize {
+    let len = entries.len();
+    entries.retain(|_, response| !is_expired(response, now));
+    len - entries.len()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Cache kept in memory for the lifetime of the process
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::dedupe::MemoryCache;
+/// use imgbb::ImgBB;
+///
+/// let imgbb = ImgBB::builder("your_api_key")
+///     .dedupe_cache(MemoryCache::new())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: Mutex<BTreeMap<ContentHash, Response>>,
+}
+
+impl MemoryCache {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached responses
+    pub fn len(&self) -> usize {
+        lock(&self.entries).len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DedupeCache for MemoryCache {
+    fn get(&self, hash: &ContentHash) -> Result<Option<Response>, Error> {
+        Ok(lock(&self.entries).get(hash).cloned())
+    }
+
+    fn insert(&self, hash: ContentHash, response: &Response) -> Result<(), Error> {
+        lock(&self.entries).insert(hash, response.clone());
+        Ok(())
+    }
+
+    fn remove(&self, hash: &ContentHash) -> Result<(), Error> {
+        lock(&self.entries).remove(hash);
+        Ok(())
+    }
+
+    fn evict_expired(&self, now: SystemTime) -> Result<usize, Error> {
+        Ok(evict_expired_entries(&mut lock(&self.entries), now))
+    }
+}
+
+/// Cache stored in a JSON file
+///
+/// The file holds an object mapping hex hashes to responses. It is read
+/// once when the cache is opened, and rewritten after every change.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::dedupe::JsonFileCache;
+/// use imgbb::ImgBB;
+///
+/// let imgbb = ImgBB::builder("your_api_key")
+///     .dedupe_cache(JsonFileCache::open("uploads.json").unwrap())
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct JsonFileCache {
+    path: PathBuf,
+    entries: Mutex<BTreeMap<ContentHash, Response>>,
+}
+
+impl JsonFileCache {
+    /// Open the cache stored at `path`
+    ///
+    /// The file is created on the first change if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid cache
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read(&path) {
+            Ok(json) => serde_json::from_slice(&json).map_err(std::io::Error::from)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Path of the cache file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write the entries to a temporary file, then move it over the cache
+    /// file, so an interrupted write does not corrupt the cache
+    fn save(&self, entries: &BTreeMap<ContentHash, Response>) -> Result<(), Error> {
+        let json = serde_json::to_vec_pretty(entries).map_err(std::io::Error::from)?;
+        let temp = self.path.with_extension("json.tmp");
+
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl DedupeCache for JsonFileCache {
+    fn get(&self, hash: &ContentHash) -> Result<Option<Response>, Error> {
+        Ok(lock(&self.entries).get(hash).cloned())
+    }
+
+    fn insert(&self, hash: ContentHash, response: &Response) -> Result<(), Error> {
+        let mut entries = lock(&self.entries);
+        entries.insert(hash, response.clone());
+        self.save(&entries)
+    }
+
+    fn remove(&self, hash: &ContentHash) -> Result<(), Error> {
+        let mut entries = lock(&self.entries);
+        if entries.remove(hash).is_some() {
+            self.save(&entries)?;
+        }
+        Ok(())
+    }
+
+    fn evict_expired(&self, now: SystemTime) -> Result<usize, Error> {
+        let mut entries = lock(&self.entries);
+        let evicted = evict_expired_entries(&mut entries, now);
+        if evicted > 0 {
+            self.save(&entries)?;
+        }
+        Ok(evicted)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteCache;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::path::Path;
+    use std::sync::Mutex;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use rusqlite::{params, Connection, OptionalExtension};
+
+    use super::{lock, ContentHash, DedupeCache};
+    use crate::model::Response;
+    use crate::Error;
+
+    /// Cache stored in an SQLite database
+    ///
+    /// Responses are kept in the `imgbb_dedupe` table, created when the
+    /// cache is opened, along with the expiration time of their image so
+    /// expired entries are evicted by a single query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::dedupe::SqliteCache;
+    /// use imgbb::ImgBB;
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .dedupe_cache(SqliteCache::open("uploads.db").unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[derive(Debug)]
+    pub struct SqliteCache {
+        connection: Mutex<Connection>,
+    }
+
+    impl SqliteCache {
+        /// Open the cache stored in the database at `path`, creating it if
+        /// needed
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the database cannot be opened
+        pub fn open<P>(path: P) -> Result<Self, Error>
+        where
+            P: AsRef<Path>,
+        {
+            Self::with_connection(Connection::open(path)?)
+        }
+
+        /// Open a cache in an in-memory database
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the database cannot be created
+        pub fn open_in_memory() -> Result<Self, Error> {
+            Self::with_connection(Connection::open_in_memory()?)
+        }
+
+        /// Use an open database connection
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the cache table cannot be created
+        pub fn with_connection(connection: Connection) -> Result<Self, Error> {
+            connection.execute_batch(
+                "CREATE TABLE IF NOT EXISTS imgbb_dedupe (
+                    hash TEXT PRIMARY KEY NOT NULL,
+                    response TEXT NOT NULL,
+                    expires_at INTEGER
+                );
+                CREATE INDEX IF NOT EXISTS imgbb_dedupe_expires_at ON imgbb_dedupe (expires_at);",
+            )?;
+
+            Ok(Self {
+                connection: Mutex::new(connection),
+            })
+        }
+    }
+
+    /// Seconds since the Unix epoch
+    fn unix_time(time: SystemTime) -> i64 {
+        time.duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() as i64)
+    }
+
+    impl DedupeCache for SqliteCache {
+        fn get(&self, hash: &ContentHash) -> Result<Option<Response>, Error> {
+            let json: Option<String> = lock(&self.connection)
+                .query_row(
+                    "SELECT response FROM imgbb_dedupe WHERE hash = ?1",
+                    params![hash.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            json.map(|json| serde_json::from_str(&json).map_err(|e| std::io::Error::from(e).into()))
+                .transpose()
+        }
+
+        fn insert(&self, hash: ContentHash, response: &Response) -> Result<(), Error> {
+            let json = serde_json::to_string(response).map_err(std::io::Error::from)?;
+            let expires_at = response
+                .data
+                .as_ref()
+                .and_then(|data| data.expires_at())
+                .map(unix_time);
+
+            lock(&self.connection).execute(
+                "INSERT OR REPLACE INTO imgbb_dedupe (hash, response, expires_at) VALUES (?1, ?2, ?3)",
+                params![hash.to_string(), json, expires_at],
+            )?;
+            Ok(())
+        }
+
+        fn remove(&self, hash: &ContentHash) -> Result<(), Error> {
+            lock(&self.connection).execute(
+                "DELETE FROM imgbb_dedupe WHERE hash = ?1",
+                params![hash.to_string()],
+            )?;
+            Ok(())
+        }
+
+        fn evict_expired(&self, now: SystemTime) -> Result<usize, Error> {
+            Ok(lock(&self.connection).execute(
+                "DELETE FROM imgbb_dedupe WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                params![unix_time(now)],
+            )?)
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::borrow::Cow;
+use std::fmt;
+
+/// Markup language of an embed snippet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmbedFormat {
+    /// HTML `<img>` tag
+    Html,
+    /// Markdown image
+    Markdown,
+    /// BBCode `[img]` tag, as used by forums
+    ///
+    /// The common BBCode dialects have no alt text, so it is left out.
+    BBCode,
+    /// reStructuredText `image` directive
+    ReStructuredText,
+}
+
+/// Size variant of an uploaded image
+///
+/// ImgBB does not create a medium or thumbnail variant for every image. When
+/// the requested variant is missing, the full size image is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ImageVariant {
+    /// Full size image
+    #[default]
+    Full,
+    /// Medium size image
+    Medium,
+    /// Thumbnail
+    Thumb,
+}
+
+/// Embed snippet for an uploaded image
+///
+/// Created with [UploadedImage::embed](crate::UploadedImage::embed) and
+/// rendered with its [Display](fmt::Display) implementation. The alt text
+/// defaults to the image title, then its name, then its ID, and is left out
+/// of BBCode snippets. Text and URLs are escaped for the chosen format.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::{EmbedFormat, ImageVariant, ImgBB};
+///
+/// async fn example() -> Result<(), imgbb::Error> {
+///     let imgbb = ImgBB::new("your_api_key");
+///     let image = imgbb.upload_file("path/to/image.jpg").await?;
+///
+///     // <a href="https://ibb.co/..."><img src="https://i.ibb.co/.../medium.jpg" alt="A cat"></a>
+///     let html = image
+///         .embed(EmbedFormat::Html)
+///         .variant(ImageVariant::Medium)
+///         .linked(true)
+///         .alt("A cat")
+///         .to_string();
+///
+///     println!("{}", html);
+///     println!("{}", image.embed(EmbedFormat::Markdown));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Embed<'a> {
+    format: EmbedFormat,
+    variant: ImageVariant,
+    linked: bool,
+    urls: EmbedUrls<'a>,
+    alt: Cow<'a, str>,
+    title: Option<Cow<'a, str>>,
+}
+
+/// URLs of an uploaded image that can appear in a snippet
+#[derive(Debug, Clone)]
+pub(crate) struct EmbedUrls<'a> {
+    pub(crate) full: &'a str,
+    pub(crate) medium: Option<&'a str>,
+    pub(crate) thumb: Option<&'a str>,
+    pub(crate) viewer: Option<&'a str>,
+}
+
+impl<'a> Embed<'a> {
+    pub(crate) fn new(format: EmbedFormat, urls: EmbedUrls<'a>, alt: &'a str) -> Self {
+        Self {
+            format,
+            variant: ImageVariant::Full,
+            linked: false,
+            urls,
+            alt: Cow::Borrowed(alt),
+            title: None,
+        }
+    }
+
+    /// Use the given size variant of the image
+    pub fn variant(mut self, variant: ImageVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Link the image to its page on the ImgBB website
+    ///
+    /// Falls back to the full size image if the viewer URL is unknown.
+    pub fn linked(mut self, linked: bool) -> Self {
+        self.linked = linked;
+        self
+    }
+
+    /// Set the alt text
+    ///
+    /// BBCode has no alt text, so it is ignored there.
+    pub fn alt<T>(mut self, alt: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.alt = Cow::Owned(alt.into());
+        self
+    }
+
+    /// Set a title, shown as a tooltip in HTML and Markdown
+    ///
+    /// BBCode and reStructuredText have no title, so it is ignored there.
+    pub fn title<T>(mut self, title: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.title = Some(Cow::Owned(title.into()));
+        self
+    }
+
+    /// URL of the embedded image
+    pub fn src(&self) -> &'a str {
+        match self.variant {
+            ImageVariant::Full => None,
+            ImageVariant::Medium => self.urls.medium,
+            ImageVariant::Thumb => self.urls.thumb,
+        }
+        .unwrap_or(self.urls.full)
+    }
+
+    /// URL the image links to, if [linked](Embed::linked)
+    pub fn href(&self) -> Option<&'a str> {
+        self.linked
+            .then(|| self.urls.viewer.unwrap_or(self.urls.full))
+    }
+}
+
+impl fmt::Display for Embed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let src = self.src();
+        let alt = single_line(&self.alt);
+        let title = self.title.as_deref().map(single_line);
+
+        match self.format {
+            EmbedFormat::Html => {
+                let mut img = format!("<img src=\"{}\" alt=\"{}\"", html_escape(src), html_escape(&alt));
+                if let Some(title) = title {
+                    img.push_str(&format!(" title=\"{}\"", html_escape(&title)));
+                }
+                img.push('>');
+
+                match self.href() {
+                    Some(href) => write!(f, "<a href=\"{}\">{}</a>", html_escape(href), img),
+                    None => f.write_str(&img),
+                }
+            }
+            EmbedFormat::Markdown => {
+                let mut img = format!("![{}]({}", markdown_escape(&alt), markdown_url(src));
+                if let Some(title) = title {
+                    img.push_str(&format!(" \"{}\"", markdown_title(&title)));
+                }
+                img.push(')');
+
+                match self.href() {
+                    Some(href) => write!(f, "[{}]({})", img, markdown_url(href)),
+                    None => f.write_str(&img),
+                }
+            }
+            EmbedFormat::BBCode => {
+                let img = format!("[img]{}[/img]", bbcode_url(src));
+
+                match self.href() {
+                    Some(href) => write!(f, "[url={}]{}[/url]", bbcode_url(href), img),
+                    None => f.write_str(&img),
+                }
+            }
+            EmbedFormat::ReStructuredText => {
+                write!(f, ".. image:: {}\n   :alt: {}", rst_url(src), rst_escape(&alt))?;
+                if let Some(href) = self.href() {
+                    write!(f, "\n   :target: {}", rst_url(href))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Collapse line breaks, which end an attribute or directive option early
+fn single_line(text: &str) -> Cow<'_, str> {
+    if text.contains(['\r', '\n']) {
+        Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Escape text for an HTML attribute value
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape text for Markdown link text
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '>' | '!') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escape the characters that start inline markup in a reStructuredText
+/// directive option
+fn rst_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`' | '|' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escape text for a quoted Markdown link title
+fn markdown_title(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Percent-encode the characters that would end a URL early in the given
+/// markup
+fn encode_url(url: &str, special: &[char]) -> String {
+    let mut encoded = String::with_capacity(url.len());
+    for c in url.chars() {
+        if c.is_whitespace() || special.contains(&c) {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+fn markdown_url(url: &str) -> String {
+    encode_url(url, &['(', ')', '<', '>'])
+}
+
+fn bbcode_url(url: &str) -> String {
+    encode_url(url, &['[', ']'])
+}
+
+fn rst_url(url: &str) -> String {
+    encode_url(url, &['`', '\\'])
+}
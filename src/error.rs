@@ -1,41 +1,432 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::format::ImageFormat;
+use crate::model::Response;
+
+/// Maximum number of body bytes kept in a [ResponseContext]
+pub const MAX_CONTEXT_BODY_LEN: usize = 2048;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("IO Error: {0}")]
     IOError(#[from] std::io::Error),
-    
+
     #[error("Reqwest Error: {0}")]
-    ReqwestError(#[from] reqwest::Error),
-    
+    ReqwestError(reqwest::Error),
+
+    #[error("Connection error: {0}")]
+    Connection(#[source] reqwest::Error),
+
     #[error("ImgBB API Error: {message}")]
     ApiError {
         message: String,
         status: Option<u16>,
-        code: Option<u16>,
+        code: Option<ApiErrorCode>,
+        context: Option<Box<ResponseContext>>,
     },
-    
+
     #[error("Missing field '{0}' in API response")]
     MissingField(String),
-    
+
     #[error("Invalid API key")]
-    InvalidApiKey,
-    
+    InvalidApiKey {
+        context: Option<Box<ResponseContext>>,
+    },
+
     #[error("Invalid base64 data")]
-    InvalidBase64Data,
-    
-    #[error("Image too large")]
-    ImageTooLarge,
-    
-    #[error("Unsupported image format")]
-    UnsupportedFormat,
-    
+    InvalidBase64Data {
+        context: Option<Box<ResponseContext>>,
+    },
+
+    #[error("Image too large{}", size_detail(size, max_size))]
+    ImageTooLarge {
+        /// Raw size of the image in bytes, if the image was rejected locally
+        size: Option<u64>,
+        /// Size limit in bytes, if the image was rejected locally
+        max_size: Option<u64>,
+        context: Option<Box<ResponseContext>>,
+    },
+
+    #[error("Unsupported image format{}", format.map(|format| format!(": {}", format)).unwrap_or_default())]
+    UnsupportedFormat {
+        /// Format detected before sending, if the image was rejected locally
+        format: Option<ImageFormat>,
+        context: Option<Box<ResponseContext>>,
+    },
+
     #[error("Request timeout")]
     Timeout,
 
     #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    RateLimitExceeded {
+        context: Option<Box<ResponseContext>>,
+    },
+
+    #[cfg(feature = "image-processing")]
+    #[error("Image processing failed: {0}")]
+    ImageProcessing(#[from] image::ImageError),
+
+    #[cfg(feature = "image-processing")]
+    #[error("Image processing task failed: {0}")]
+    ProcessingTask(String),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("Image uploaded, but not recorded in the ledger: {source}")]
+    Ledger {
+        /// Response of the upload, holding the delete URL of the image
+        response: Box<Response>,
+        source: Box<Error>,
+    },
+
+    #[error("Invalid or missing parameters: {message}")]
+    InvalidParameters {
+        message: String,
+        context: Option<Box<ResponseContext>>,
+    },
+}
+
+/// Sizes shown in the message of an [ImageTooLarge](Error::ImageTooLarge) error
+fn size_detail(size: &Option<u64>, max_size: &Option<u64>) -> String {
+    match (size, max_size) {
+        (Some(size), Some(max_size)) => {
+            format!(": {} bytes, the limit is {} bytes", size, max_size)
+        }
+        _ => String::new(),
+    }
+}
+
+/// Whether a reqwest error was caused by the connection being reset or
+/// closed while the request or response was in flight
+fn is_connection_reset(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        if let Some(io) = error.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            );
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Classify reqwest errors, so timeouts and connection failures can be
+/// matched without inspecting the reqwest error
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Error::Timeout
+        } else if error.is_connect() {
+            Error::Connection(error)
+        } else {
+            Error::ReqwestError(error)
+        }
+    }
+}
+
+impl Error {
+    /// Build an [InvalidParameters](Error::InvalidParameters) error for a
+    /// problem detected before any request was sent
+    pub(crate) fn invalid_parameters<T>(message: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Error::InvalidParameters {
+            message: message.into(),
+            context: None,
+        }
+    }
+
+    /// Build an [ImageTooLarge](Error::ImageTooLarge) error for an image
+    /// rejected before it was sent
+    pub(crate) fn image_too_large(size: u64, max_size: u64) -> Self {
+        Error::ImageTooLarge {
+            size: Some(size),
+            max_size: Some(max_size),
+            context: None,
+        }
+    }
+
+    /// The API response that caused this error, if any
+    pub fn context(&self) -> Option<&ResponseContext> {
+        match self {
+            Error::ApiError { context, .. }
+            | Error::InvalidApiKey { context }
+            | Error::InvalidBase64Data { context }
+            | Error::ImageTooLarge { context, .. }
+            | Error::UnsupportedFormat { context, .. }
+            | Error::RateLimitExceeded { context }
+            | Error::InvalidParameters { context, .. } => context.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the request may succeed if it is sent again
+    ///
+    /// True for timeouts, connection failures, connections reset while the
+    /// request or response body was in flight, rate limiting and server
+    /// errors. Other request failures, such as malformed responses, are not
+    /// retryable. This is what [RetryPolicy](crate::RetryPolicy) retries by default.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Timeout | Error::Connection(_) | Error::RateLimitExceeded { .. } => true,
+            Error::ReqwestError(e) => {
+                e.is_connect()
+                    || e.is_timeout()
+                    || is_connection_reset(e)
+                    || e.status().is_some_and(|status| status.is_server_error())
+            }
+            Error::ApiError {
+                status: Some(status),
+                ..
+            } => *status >= 500 || *status == 429,
+            _ => false,
+        }
+    }
+
+    /// Whether the request was rejected because of something the caller
+    /// must fix, such as the API key, the image or the parameters
+    ///
+    /// Rate limiting is not considered a client error, as it is retryable.
+    pub fn is_client_error(&self) -> bool {
+        match self {
+            Error::InvalidApiKey { .. }
+            | Error::InvalidBase64Data { .. }
+            | Error::ImageTooLarge { .. }
+            | Error::UnsupportedFormat { .. }
+            | Error::InvalidParameters { .. } => true,
+            _ => self
+                .status()
+                .is_some_and(|status| (400..500).contains(&status) && status != 429),
+        }
+    }
+
+    /// ImgBB error code reported by the API, if any
+    pub fn api_code(&self) -> Option<ApiErrorCode> {
+        match self {
+            Error::ApiError {
+                code: Some(code), ..
+            } => Some(*code),
+            _ => self.context().and_then(|context| context.code),
+        }
+    }
+
+    /// HTTP status of the response that caused this error, if any
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::ApiError {
+                status: Some(status),
+                ..
+            } => Some(*status),
+            Error::ReqwestError(e) => e.status().map(|status| status.as_u16()),
+            _ => self.context().map(|context| context.status),
+        }
+    }
+}
+
+/// Error code reported by the ImgBB API
+///
+/// Codes without a named variant are kept as [Unknown](ApiErrorCode::Unknown).
+/// Codes serialize as their numeric value.
+///
+/// # Examples
+///
+/// ```rust
+/// use imgbb::ApiErrorCode;
+///
+/// let code = ApiErrorCode::from(313);
+/// assert_eq!(code, ApiErrorCode::ImageTooLarge);
+/// assert_eq!(code.as_u16(), 313);
+/// assert_eq!(ApiErrorCode::from(999), ApiErrorCode::Unknown(999));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "u16", into = "u16")]
+pub enum ApiErrorCode {
+    /// 100: the API key is missing or invalid
+    InvalidApiKey,
+    /// 120: the image is not a valid base64 string
+    InvalidBase64,
+    /// 130: no upload source was sent
+    EmptySource,
+    /// 310: the upload source is not a valid image or URL
+    InvalidSource,
+    /// 313: the image exceeds the size limit
+    ImageTooLarge,
+    /// 400: the request is malformed or has invalid parameters
+    BadRequest,
+    /// 401: the image format is not supported
+    UnsupportedFormat,
+    /// 429: too many requests were sent
+    RateLimited,
+    /// Any other code
+    Unknown(u16),
+}
+
+impl ApiErrorCode {
+    /// Numeric value of the code
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            ApiErrorCode::InvalidApiKey => 100,
+            ApiErrorCode::InvalidBase64 => 120,
+            ApiErrorCode::EmptySource => 130,
+            ApiErrorCode::InvalidSource => 310,
+            ApiErrorCode::ImageTooLarge => 313,
+            ApiErrorCode::BadRequest => 400,
+            ApiErrorCode::UnsupportedFormat => 401,
+            ApiErrorCode::RateLimited => 429,
+            ApiErrorCode::Unknown(code) => *code,
+        }
+    }
+
+    /// Human readable description of the code
+    pub fn description(&self) -> &'static str {
+        match self {
+            ApiErrorCode::InvalidApiKey => "Missing or invalid API key",
+            ApiErrorCode::InvalidBase64 => "Invalid base64 image data",
+            ApiErrorCode::EmptySource => "Empty upload source",
+            ApiErrorCode::InvalidSource => "Invalid image source",
+            ApiErrorCode::ImageTooLarge => "Image exceeds the size limit",
+            ApiErrorCode::BadRequest => "Bad request",
+            ApiErrorCode::UnsupportedFormat => "Unsupported image format",
+            ApiErrorCode::RateLimited => "Too many requests",
+            ApiErrorCode::Unknown(_) => "Unknown error code",
+        }
+    }
+}
+
+impl From<u16> for ApiErrorCode {
+    fn from(code: u16) -> Self {
+        match code {
+            100 => ApiErrorCode::InvalidApiKey,
+            120 => ApiErrorCode::InvalidBase64,
+            130 => ApiErrorCode::EmptySource,
+            310 => ApiErrorCode::InvalidSource,
+            313 => ApiErrorCode::ImageTooLarge,
+            400 => ApiErrorCode::BadRequest,
+            401 => ApiErrorCode::UnsupportedFormat,
+            429 => ApiErrorCode::RateLimited,
+            code => ApiErrorCode::Unknown(code),
+        }
+    }
+}
+
+impl From<ApiErrorCode> for u16 {
+    fn from(code: ApiErrorCode) -> Self {
+        code.as_u16()
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for ApiErrorCode {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ApiErrorCode".into()
+    }
+
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        u16::json_schema(generator)
+    }
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.as_u16(), self.description())
+    }
+}
+
+/// Kind of request that received a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    /// Image upload
+    Upload,
+    /// Image deletion
+    Delete,
+    /// Check that an uploaded image still exists
+    Check,
+}
+
+impl fmt::Display for RequestKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestKind::Upload => f.write_str("upload"),
+            RequestKind::Delete => f.write_str("delete"),
+            RequestKind::Check => f.write_str("check"),
+        }
+    }
+}
+
+/// What the API actually returned for a failed request
+///
+/// Only the `Retry-After` and rate limit headers are kept, and the body is
+/// truncated to [MAX_CONTEXT_BODY_LEN] bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseContext {
+    /// Kind of request that failed
+    pub kind: RequestKind,
+    /// HTTP status code
+    pub status: u16,
+    /// ImgBB error code from the body, if any
+    pub code: Option<ApiErrorCode>,
+    /// Selected response headers, with lowercase names
+    pub headers: Vec<(String, String)>,
+    /// Raw response body, possibly truncated
+    pub body: String,
+    /// Whether the body was longer than [MAX_CONTEXT_BODY_LEN] bytes
+    pub truncated: bool,
+}
+
+impl ResponseContext {
+    /// Capture the relevant parts of a response
+    pub(crate) fn new(
+        kind: RequestKind,
+        status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+        body: &str,
+    ) -> Self {
+        let headers = headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.as_str();
+                name == "retry-after" || name.starts_with("x-ratelimit") || name.starts_with("ratelimit")
+            })
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+
+        let mut end = body.len().min(MAX_CONTEXT_BODY_LEN);
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        Self {
+            kind,
+            status: status.as_u16(),
+            code: None,
+            headers,
+            body: body[..end].to_string(),
+            truncated: end < body.len(),
+        }
+    }
 
-    #[error("Invalid or missing parameters: {0}")]
-    InvalidParameters(String),
+    /// Value of a kept header, looked up case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
 }
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use crate::Error;
+
+/// Shortest expiration accepted by ImgBB
+pub const MIN_EXPIRATION: Duration = Duration::from_secs(60);
+/// Longest expiration accepted by ImgBB (180 days)
+pub const MAX_EXPIRATION: Duration = Duration::from_secs(15_552_000);
+
+/// Time after which ImgBB deletes an uploaded image
+///
+/// Created from a number of seconds or a [Duration]. Only whole seconds are
+/// sent to the API, and the value must lie between [MIN_EXPIRATION] and
+/// [MAX_EXPIRATION]. Out of range values are rejected before the upload is
+/// sent.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use imgbb::ImgBB;
+///
+/// async fn example() -> Result<(), imgbb::Error> {
+///     let imgbb = ImgBB::new("your_api_key");
+///
+///     let image = imgbb.upload_builder()
+///         .file("path/to/image.jpg")?
+///         .expiration(Duration::from_secs(24 * 60 * 60))
+///         .upload()
+///         .await?;
+///
+///     println!("Expires at {:?}", image.expires_at());
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Expiration(Duration);
+
+impl Expiration {
+    /// Expiration after the given number of seconds
+    pub fn from_secs(secs: u64) -> Self {
+        Self(Duration::from_secs(secs))
+    }
+
+    /// Expiration as a [Duration]
+    pub fn as_duration(&self) -> Duration {
+        self.0
+    }
+
+    /// Whole seconds sent to the API
+    pub fn as_secs(&self) -> u64 {
+        self.0.as_secs()
+    }
+
+    /// Check the expiration against the range accepted by ImgBB
+    ///
+    /// # Errors
+    ///
+    /// Returns [InvalidParameters](Error::InvalidParameters) if the
+    /// expiration is shorter than [MIN_EXPIRATION] or longer than
+    /// [MAX_EXPIRATION]
+    pub fn validate(&self) -> Result<u64, Error> {
+        let secs = self.as_secs();
+
+        if secs < MIN_EXPIRATION.as_secs() || secs > MAX_EXPIRATION.as_secs() {
+            return Err(Error::invalid_parameters(format!(
+                "Expiration must be between {} and {} seconds, got {}",
+                MIN_EXPIRATION.as_secs(),
+                MAX_EXPIRATION.as_secs(),
+                secs
+            )));
+        }
+
+        Ok(secs)
+    }
+}
+
+impl From<u64> for Expiration {
+    fn from(secs: u64) -> Self {
+        Self::from_secs(secs)
+    }
+}
+
+impl From<Duration> for Expiration {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
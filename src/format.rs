@@ -0,0 +1,192 @@
+use std::fmt;
+
+use base64::engine::{general_purpose, Engine};
+
+/// Number of leading bytes inspected when detecting a format
+const SNIFF_LEN: usize = 256;
+
+/// Image format detected from the leading bytes of the data
+///
+/// # Examples
+///
+/// ```rust
+/// use imgbb::ImageFormat;
+///
+/// let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+/// let format = ImageFormat::detect(&png).unwrap();
+///
+/// assert_eq!(format, ImageFormat::Png);
+/// assert_eq!(format.mime_type(), "image/png");
+/// assert!(format.is_supported());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    /// JPEG
+    Jpeg,
+    /// PNG
+    Png,
+    /// GIF
+    Gif,
+    /// Windows bitmap
+    Bmp,
+    /// WebP
+    Webp,
+    /// TIFF
+    Tiff,
+    /// HEIC/HEIF
+    Heic,
+    /// AVIF
+    Avif,
+    /// Windows icon, not accepted by ImgBB
+    Ico,
+    /// SVG, not accepted by ImgBB
+    Svg,
+    /// Photoshop document, not accepted by ImgBB
+    Psd,
+    /// JPEG XL, not accepted by ImgBB
+    JpegXl,
+}
+
+impl ImageFormat {
+    /// Detect the format of image data from its magic bytes
+    ///
+    /// Only the first few hundred bytes are inspected. Returns `None` if the
+    /// format is not recognized.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let data = &data[..data.len().min(SNIFF_LEN)];
+
+        if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else if data.starts_with(b"\x89PNG\r\n\x1A\n") {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else if data.starts_with(b"BM") && data.len() >= 14 {
+            Some(ImageFormat::Bmp)
+        } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+            Some(ImageFormat::Webp)
+        } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+            Some(ImageFormat::Tiff)
+        } else if data.get(4..8) == Some(b"ftyp") {
+            Self::detect_heif(data)
+        } else if data.starts_with(&[0, 0, 1, 0]) {
+            Some(ImageFormat::Ico)
+        } else if data.starts_with(b"8BPS") {
+            Some(ImageFormat::Psd)
+        } else if data.starts_with(&[0xFF, 0x0A]) || data.starts_with(b"\0\0\0\x0CJXL \r\n\x87\n") {
+            Some(ImageFormat::JpegXl)
+        } else if is_svg(data) {
+            Some(ImageFormat::Svg)
+        } else {
+            None
+        }
+    }
+
+    /// Detect the format of base64 encoded image data
+    ///
+    /// Only the start of the data is decoded. Returns `None` if it is not
+    /// valid base64 or the format is not recognized.
+    pub fn detect_base64(data: &str) -> Option<Self> {
+        // Whole groups of four characters decode without padding
+        let end = data.len().min(SNIFF_LEN / 3 * 4);
+        let prefix = data.get(..end - end % 4)?;
+
+        Self::detect(&general_purpose::STANDARD.decode(prefix).ok()?)
+    }
+
+    /// Pick HEIC or AVIF from the brands of an ISO media file
+    fn detect_heif(data: &[u8]) -> Option<Self> {
+        let size = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+        let ftyp = data.get(8..size.min(data.len()))?;
+
+        // Major brand, then the compatible brands after the minor version
+        let brands = ftyp
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|(index, _)| *index != 1)
+            .map(|(_, brand)| brand);
+
+        let mut format = None;
+        for brand in brands {
+            match brand {
+                b"avif" | b"avis" => return Some(ImageFormat::Avif),
+                b"heic" | b"heix" | b"hevc" | b"hevx" | b"heim" | b"heis" | b"mif1" | b"msf1" => {
+                    format = Some(ImageFormat::Heic)
+                }
+                _ => {}
+            }
+        }
+        format
+    }
+
+    /// Whether ImgBB accepts images in this format
+    pub fn is_supported(&self) -> bool {
+        !matches!(
+            self,
+            ImageFormat::Ico | ImageFormat::Svg | ImageFormat::Psd | ImageFormat::JpegXl
+        )
+    }
+
+    /// MIME type of the format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Bmp => "image/bmp",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Tiff => "image/tiff",
+            ImageFormat::Heic => "image/heic",
+            ImageFormat::Avif => "image/avif",
+            ImageFormat::Ico => "image/vnd.microsoft.icon",
+            ImageFormat::Svg => "image/svg+xml",
+            ImageFormat::Psd => "image/vnd.adobe.photoshop",
+            ImageFormat::JpegXl => "image/jxl",
+        }
+    }
+
+    /// Usual file extension of the format, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Tiff => "tiff",
+            ImageFormat::Heic => "heic",
+            ImageFormat::Avif => "avif",
+            ImageFormat::Ico => "ico",
+            ImageFormat::Svg => "svg",
+            ImageFormat::Psd => "psd",
+            ImageFormat::JpegXl => "jxl",
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ImageFormat::Jpeg => "JPEG",
+            ImageFormat::Png => "PNG",
+            ImageFormat::Gif => "GIF",
+            ImageFormat::Bmp => "BMP",
+            ImageFormat::Webp => "WebP",
+            ImageFormat::Tiff => "TIFF",
+            ImageFormat::Heic => "HEIC",
+            ImageFormat::Avif => "AVIF",
+            ImageFormat::Ico => "ICO",
+            ImageFormat::Svg => "SVG",
+            ImageFormat::Psd => "PSD",
+            ImageFormat::JpegXl => "JPEG XL",
+        })
+    }
+}
+
+/// Whether text data starts with an SVG document
+fn is_svg(data: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data));
+    let text = text.trim_start();
+
+    text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg"))
+}
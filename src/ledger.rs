@@ -0,0 +1,603 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::dedupe::ContentHash;
+use crate::model::Response;
+use crate::Error;
+
+/// Record of a successful upload
+///
+/// Holds the delete URL of the image, so it can be deleted long after the
+/// upload, along with where the image came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// ID of the image
+    pub id: String,
+    /// Direct URL of the image
+    pub url: Option<String>,
+    /// URL of the image page on the ImgBB website
+    pub url_viewer: Option<String>,
+    /// Display URL of the image
+    pub display_url: Option<String>,
+    /// URL deleting the image
+    pub delete_url: Option<String>,
+    /// SHA-256 hash of the uploaded bytes, unknown for streams and URLs
+    pub hash: Option<ContentHash>,
+    /// Path of the uploaded file
+    pub source_path: Option<PathBuf>,
+    /// Name of the image
+    pub name: Option<String>,
+    /// Title of the image
+    pub title: Option<String>,
+    /// Album the image was added to
+    pub album: Option<String>,
+    /// Upload time, in seconds since the Unix epoch
+    pub time: u64,
+    /// Lifetime in seconds, or `None` if the image does not expire
+    pub expiration: Option<u64>,
+    /// Whether the image is still on ImgBB, as last seen by
+    /// [ImgBB::sweep](crate::ImgBB::sweep)
+    #[serde(default)]
+    pub status: EntryStatus,
+}
+
+/// Whether a recorded image is still on ImgBB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    /// The image is assumed to exist
+    #[default]
+    Active,
+    /// The expiration of the image has passed
+    Expired,
+    /// The image no longer exists on ImgBB, even though it had not expired
+    Missing,
+}
+
+impl LedgerEntry {
+    /// Time the image was uploaded
+    pub fn uploaded_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.time)
+    }
+
+    /// Time the image expires, or `None` if it does not expire
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.uploaded_at()
+            .checked_add(Duration::from_secs(self.expiration?))
+    }
+
+    /// Whether the image has expired at `now`
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at().is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Where an uploaded image came from, recorded along with the response
+#[derive(Debug, Default)]
+pub(crate) struct Provenance {
+    pub(crate) hash: Option<ContentHash>,
+    pub(crate) source_path: Option<PathBuf>,
+    pub(crate) name: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) album: Option<String>,
+}
+
+/// Record an upload response in the ledger
+///
+/// Responses without an image ID are not recorded. The image is already on
+/// ImgBB when this runs, so a failure is returned as
+/// [Error::Ledger](Error::Ledger), which keeps the response.
+pub(crate) fn record(
+    ledger: &dyn UploadLedger,
+    response: &Response,
+    provenance: Provenance,
+) -> Result<(), Error> {
+    let Some(data) = &response.data else {
+        return Ok(());
+    };
+    let Some(id) = data.id.clone() else {
+        return Ok(());
+    };
+
+    let time = data.time.unwrap_or_else(|| unix_time(SystemTime::now()));
+
+    let entry = LedgerEntry {
+        id,
+        url: data.url.clone(),
+        url_viewer: data.url_viewer.clone(),
+        display_url: data.display_url.clone(),
+        delete_url: data.delete_url.clone(),
+        hash: provenance.hash,
+        source_path: provenance.source_path,
+        name: provenance
+            .name
+            .or_else(|| data.image.as_ref().and_then(|image| image.name.clone())),
+        title: provenance.title.or_else(|| data.title.clone()),
+        album: provenance.album,
+        time,
+        expiration: data.expiration.filter(|&expiration| expiration > 0),
+        status: EntryStatus::Active,
+    };
+    ledger.record(&entry).map_err(|e| Error::Ledger {
+        response: Box::new(response.clone()),
+        source: Box::new(e),
+    })
+}
+
+/// Delete URL of a recorded image
+pub(crate) fn delete_url(entry: &LedgerEntry) -> Result<&str, Error> {
+    entry.delete_url.as_deref().ok_or_else(|| {
+        Error::invalid_parameters(format!("No delete URL recorded for image {}", entry.id))
+    })
+}
+
+/// Remove the entry of a deleted image from the ledger, if there is one
+pub(crate) fn forget(ledger: Option<&dyn UploadLedger>, entry: &LedgerEntry) -> Result<(), Error> {
+    if let Some(ledger) = ledger {
+        ledger.remove(&[entry.id.as_str()])?;
+    }
+    Ok(())
+}
+
+/// Seconds since the Unix epoch
+pub(crate) fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Persistent record of the uploads made by a client
+///
+/// When a client has a ledger, set with
+/// [ImgBBBuilder::ledger](crate::ImgBBBuilder::ledger), every successful
+/// upload is recorded with its delete URL. Uploads answered by a
+/// [DedupeCache](crate::DedupeCache) are not recorded again. Recorded
+/// images can be deleted with
+/// [ImgBB::delete_recorded](crate::ImgBB::delete_recorded).
+///
+/// Implementations are provided for a JSON lines file and, with the
+/// `sqlite` feature, an SQLite database. The queries filter
+/// [entries](UploadLedger::entries) unless an implementation provides a
+/// faster lookup.
+pub trait UploadLedger: fmt::Debug + Send + Sync {
+    /// Record an upload, replacing any entry with the same ID
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be written
+    fn record(&self, entry: &LedgerEntry) -> Result<(), Error>;
+
+    /// All entries, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be read
+    fn entries(&self) -> Result<Vec<LedgerEntry>, Error>;
+
+    /// Remove the entries with the given IDs
+    ///
+    /// Returns the number of removed entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be written
+    fn remove(&self, ids: &[&str]) -> Result<usize, Error>;
+
+    /// Entry of the image with the given ID
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be read
+    fn find_by_id(&self, id: &str) -> Result<Option<LedgerEntry>, Error> {
+        Ok(self.entries()?.into_iter().find(|entry| entry.id == id))
+    }
+
+    /// Entries of the uploads of the given bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be read
+    fn find_by_hash(&self, hash: &ContentHash) -> Result<Vec<LedgerEntry>, Error> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| entry.hash.as_ref() == Some(hash))
+            .collect())
+    }
+
+    /// Entries of the uploads of the file at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be read
+    fn find_by_source(&self, path: &Path) -> Result<Vec<LedgerEntry>, Error> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| entry.source_path.as_deref() == Some(path))
+            .collect())
+    }
+
+    /// Entries of the images uploaded from `start`, inclusive, to `end`,
+    /// exclusive
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be read
+    fn find_between(&self, start: SystemTime, end: SystemTime) -> Result<Vec<LedgerEntry>, Error> {
+        let (start, end) = (unix_time(start), unix_time(end));
+
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| (start..end).contains(&entry.time))
+            .collect())
+    }
+}
+
+impl<T> UploadLedger for Arc<T>
+where
+    T: UploadLedger + ?Sized,
+{
+    fn record(&self, entry: &LedgerEntry) -> Result<(), Error> {
+        (**self).record(entry)
+    }
+
+    fn entries(&self) -> Result<Vec<LedgerEntry>, Error> {
+        (**self).entries()
+    }
+
+    fn remove(&self, ids: &[&str]) -> Result<usize, Error> {
+        (**self).remove(ids)
+    }
+
+    fn find_by_id(&self, id: &str) -> Result<Option<LedgerEntry>, Error> {
+        (**self).find_by_id(id)
+    }
+
+    fn find_by_hash(&self, hash: &ContentHash) -> Result<Vec<LedgerEntry>, Error> {
+        (**self).find_by_hash(hash)
+    }
+
+    fn find_by_source(&self, path: &Path) -> Result<Vec<LedgerEntry>, Error> {
+        (**self).find_by_source(path)
+    }
+
+    fn find_between(&self, start: SystemTime, end: SystemTime) -> Result<Vec<LedgerEntry>, Error> {
+        (**self).find_between(start, end)
+    }
+}
+
+/// Number of appends between checks for superseded lines in a
+/// [JsonLinesLedger]
+const COMPACT_INTERVAL: usize = 64;
+
+/// Ledger stored in a JSON lines file
+///
+/// Every upload appends one JSON object to the file. Entries recorded again
+/// with the same ID replace the earlier line when the ledger is read.
+/// Removing entries rewrites the file, and so does recording once most
+/// lines have been replaced, keeping the file close to one line per entry.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::ledger::JsonLinesLedger;
+/// use imgbb::ImgBB;
+///
+/// let imgbb = ImgBB::builder("your_api_key")
+///     .ledger(JsonLinesLedger::open("uploads.jsonl"))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct JsonLinesLedger {
+    path: PathBuf,
+    /// Serializes writes made through this ledger, counting the appends
+    /// since the file was last checked for superseded lines
+    lock: Mutex<usize>,
+}
+
+impl JsonLinesLedger {
+    /// Use the ledger stored at `path`
+    ///
+    /// The file is created on the first upload if it does not exist.
+    pub fn open<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(0),
+        }
+    }
+
+    /// Path of the ledger file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Entries of the file, along with its number of lines
+    fn read(&self) -> Result<(Vec<LedgerEntry>, usize), Error> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries: Vec<LedgerEntry> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut lines = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            lines += 1;
+
+            let entry: LedgerEntry = serde_json::from_str(&line).map_err(std::io::Error::from)?;
+            match positions.get(&entry.id) {
+                Some(&position) => entries[position] = entry,
+                None => {
+                    positions.insert(entry.id.clone(), entries.len());
+                    entries.push(entry);
+                }
+            }
+        }
+        Ok((entries, lines))
+    }
+
+    /// Rewrite the file if most of its lines have been replaced
+    fn compact(&self) -> Result<(), Error> {
+        let (entries, lines) = self.read()?;
+        if lines > entries.len() * 2 {
+            self.write(&entries)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the file with one line per entry
+    fn write(&self, entries: &[LedgerEntry]) -> Result<(), Error> {
+        let mut json = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut json, entry).map_err(std::io::Error::from)?;
+            json.push(b'\n');
+        }
+
+        // Write the entries aside, so an interrupted rewrite does not lose
+        // the ledger
+        let temp = self.path.with_extension("jsonl.tmp");
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl UploadLedger for JsonLinesLedger {
+    fn record(&self, entry: &LedgerEntry) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(entry).map_err(std::io::Error::from)?;
+        line.push(b'\n');
+
+        let mut appended = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(&line)?;
+
+        *appended += 1;
+        if *appended >= COMPACT_INTERVAL {
+            *appended = 0;
+            // The entry is already recorded, and compacting is tried again
+            // after the next appends
+            let _ = self.compact();
+        }
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<LedgerEntry>, Error> {
+        Ok(self.read()?.0)
+    }
+
+    fn remove(&self, ids: &[&str]) -> Result<usize, Error> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let (entries, lines) = self.read()?;
+        let len = entries.len();
+        let kept: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| !ids.contains(&entry.id.as_str()))
+            .collect();
+
+        // Rewriting also drops the superseded lines
+        let removed = len - kept.len();
+        if removed > 0 || lines > len {
+            self.write(&kept)?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteLedger;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::path::Path;
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::SystemTime;
+
+    use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+
+    use super::{unix_time, LedgerEntry, UploadLedger};
+    use crate::dedupe::ContentHash;
+    use crate::Error;
+
+    /// Ledger stored in an SQLite database
+    ///
+    /// Entries are kept in the `imgbb_ledger` table, created when the ledger
+    /// is opened, with indexed columns for every query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ledger::SqliteLedger;
+    /// use imgbb::ImgBB;
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .ledger(SqliteLedger::open("uploads.db").unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[derive(Debug)]
+    pub struct SqliteLedger {
+        connection: Mutex<Connection>,
+    }
+
+    impl SqliteLedger {
+        /// Open the ledger stored in the database at `path`, creating it if
+        /// needed
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the database cannot be opened
+        pub fn open<P>(path: P) -> Result<Self, Error>
+        where
+            P: AsRef<Path>,
+        {
+            Self::with_connection(Connection::open(path)?)
+        }
+
+        /// Open a ledger in an in-memory database
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the database cannot be created
+        pub fn open_in_memory() -> Result<Self, Error> {
+            Self::with_connection(Connection::open_in_memory()?)
+        }
+
+        /// Use an open database connection
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the ledger table cannot be created
+        pub fn with_connection(connection: Connection) -> Result<Self, Error> {
+            connection.execute_batch(
+                "CREATE TABLE IF NOT EXISTS imgbb_ledger (
+                    id TEXT PRIMARY KEY NOT NULL,
+                    entry TEXT NOT NULL,
+                    hash TEXT,
+                    source_path TEXT,
+                    time INTEGER NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS imgbb_ledger_hash ON imgbb_ledger (hash);
+                CREATE INDEX IF NOT EXISTS imgbb_ledger_source_path ON imgbb_ledger (source_path);
+                CREATE INDEX IF NOT EXISTS imgbb_ledger_time ON imgbb_ledger (time);",
+            )?;
+
+            Ok(Self {
+                connection: Mutex::new(connection),
+            })
+        }
+
+        fn connection(&self) -> MutexGuard<'_, Connection> {
+            self.connection.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        /// Entries of the rows matching a condition, oldest first
+        fn select<P>(&self, condition: &str, params: P) -> Result<Vec<LedgerEntry>, Error>
+        where
+            P: rusqlite::Params,
+        {
+            let connection = self.connection();
+            let mut statement = connection.prepare(&format!(
+                "SELECT entry FROM imgbb_ledger WHERE {} ORDER BY time, rowid",
+                condition
+            ))?;
+
+            let rows = statement.query_map(params, |row| row.get::<_, String>(0))?;
+            let mut entries = Vec::new();
+            for json in rows {
+                entries.push(parse(&json?)?);
+            }
+            Ok(entries)
+        }
+    }
+
+    fn parse(json: &str) -> Result<LedgerEntry, Error> {
+        Ok(serde_json::from_str(json).map_err(std::io::Error::from)?)
+    }
+
+    impl UploadLedger for SqliteLedger {
+        fn record(&self, entry: &LedgerEntry) -> Result<(), Error> {
+            let json = serde_json::to_string(entry).map_err(std::io::Error::from)?;
+
+            self.connection().execute(
+                "INSERT OR REPLACE INTO imgbb_ledger (id, entry, hash, source_path, time)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    entry.id,
+                    json,
+                    entry.hash.map(|hash| hash.to_string()),
+                    entry
+                        .source_path
+                        .as_ref()
+                        .map(|path| path.to_string_lossy().into_owned()),
+                    entry.time as i64,
+                ],
+            )?;
+            Ok(())
+        }
+
+        fn entries(&self) -> Result<Vec<LedgerEntry>, Error> {
+            self.select("1", [])
+        }
+
+        fn remove(&self, ids: &[&str]) -> Result<usize, Error> {
+            if ids.is_empty() {
+                return Ok(0);
+            }
+
+            let placeholders = vec!["?"; ids.len()].join(", ");
+            Ok(self.connection().execute(
+                &format!("DELETE FROM imgbb_ledger WHERE id IN ({})", placeholders),
+                params_from_iter(ids),
+            )?)
+        }
+
+        fn find_by_id(&self, id: &str) -> Result<Option<LedgerEntry>, Error> {
+            let json: Option<String> = self
+                .connection()
+                .query_row(
+                    "SELECT entry FROM imgbb_ledger WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            json.as_deref().map(parse).transpose()
+        }
+
+        fn find_by_hash(&self, hash: &ContentHash) -> Result<Vec<LedgerEntry>, Error> {
+            self.select("hash = ?1", params![hash.to_string()])
+        }
+
+        fn find_by_source(&self, path: &Path) -> Result<Vec<LedgerEntry>, Error> {
+            self.select("source_path = ?1", params![path.to_string_lossy()])
+        }
+
+        fn find_between(&self, start: SystemTime, end: SystemTime) -> Result<Vec<LedgerEntry>, Error> {
+            self.select(
+                "time >= ?1 AND time < ?2",
+                params![unix_time(start) as i64, unix_time(end) as i64],
+            )
+        }
+    }
+}
@@ -1,23 +1,107 @@
 // Constants for API endpoints and configuration
-const IMGBB_API_URL: &str = "https://api.imgbb.com/1/upload";
-static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
+/// Chunk size used when streaming readers and files
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// Default base URL of the ImgBB API
+pub const DEFAULT_BASE_URL: &str = "https://api.imgbb.com/1";
+/// Default limit on the raw size of an image, matching ImgBB's 32 MB limit
+pub const DEFAULT_MAX_IMAGE_SIZE: u64 = 32 * 1024 * 1024;
+pub(crate) static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
 use base64::engine::{general_purpose, Engine};
-use std::path::Path;
+use bytes::Bytes;
+use futures_util::stream::{self, Stream, StreamExt};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncRead;
+use tokio_util::io::ReaderStream;
 use std::time::Duration;
 
 /// Module for ImgBB API error
 pub mod error;
-pub use error::Error;
+pub use error::{ApiErrorCode, Error, RequestKind, ResponseContext};
 
 /// Module for ImgBB API response model
 pub mod model;
 use model::*;
+pub use model::UploadedImage;
+
+/// Module for embed snippets
+pub mod embed;
+pub use embed::{Embed, EmbedFormat, ImageVariant};
+
+/// Module for image format detection
+pub mod format;
+pub use format::ImageFormat;
+
+/// Module for removing metadata from images
+pub mod metadata;
+pub use metadata::{MetadataKind, StripOptions, StrippedMetadata};
+
+/// Module for shrinking images to fit upload limits
+#[cfg(feature = "image-processing")]
+pub mod processing;
+#[cfg(feature = "image-processing")]
+pub use processing::{FitPolicy, Transformation};
+
+/// Module for image expiration
+pub mod expiration;
+pub use expiration::Expiration;
 
 /// Module for ImgBB uploader
 pub mod uploader;
 use uploader::*;
 
+/// Module for retrying failed requests
+pub mod retry;
+pub use retry::RetryPolicy;
+
+/// Module for batch uploads
+pub mod batch;
+pub use batch::UploadSpec;
+
+/// Module for skipping uploads of images uploaded before
+pub mod dedupe;
+pub use dedupe::DedupeCache;
+use dedupe::ContentHash;
+
+/// Module for recording uploads and their delete URLs
+pub mod ledger;
+pub use ledger::{EntryStatus, LedgerEntry, UploadLedger};
+use ledger::Provenance;
+
+/// Module for reconciling the ledger with ImgBB
+pub mod sweep;
+pub use sweep::{SweepPolicy, SweepReport};
+use sweep::Step;
+
+/// Module for client-side rate limiting
+pub mod rate_limit;
+pub use rate_limit::RateLimiter;
+
+/// Module for upload progress reporting
+pub mod progress;
+pub use progress::{BatchProgress, Progress};
+use progress::{BatchTracker, ProgressObservers};
+
+/// Module for interpreting API responses
+mod response;
+use response::{parse_check_response, parse_delete_response, parse_upload_response};
+
+/// Module for upload transports
+pub mod transport;
+pub use transport::Transport;
+use transport::{ByteStream, RequestBody};
+
+/// Module for the blocking ImgBB client
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+/// Build the upload endpoint for the given API base URL
+pub(crate) fn upload_endpoint(base_url: &str) -> String {
+    format!("{}/upload", base_url.trim_end_matches('/'))
+}
+
 /// Main client for interacting with the ImgBB API
 ///
 /// The `ImgBB` struct provides methods for uploading and deleting images
@@ -35,10 +119,10 @@ use uploader::*;
 ///     let imgbb = ImgBB::new("your_api_key");
 ///
 ///     // Upload an image file
-///     let response = imgbb.upload_file("path/to/image.jpg").await?;
+///     let image = imgbb.upload_file("path/to/image.jpg").await?;
 ///
 ///     // Print the image URL
-///     println!("Image URL: {}", response.data.unwrap().url.unwrap());
+///     println!("Image URL: {}", image.url);
 ///
 ///     Ok(())
 /// }
@@ -46,6 +130,12 @@ use uploader::*;
 pub struct ImgBB {
     client: reqwest::Client,
     api_key: String,
+    base_url: String,
+    retry: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    max_image_size: u64,
+    dedupe: Option<Arc<dyn DedupeCache>>,
+    ledger: Option<Arc<dyn UploadLedger>>,
 }
 
 /// Builder for creating a customized ImgBB client
@@ -72,6 +162,12 @@ pub struct ImgBBBuilder {
     timeout: Option<Duration>,
     user_agent: Option<String>,
     client: Option<reqwest::Client>,
+    base_url: Option<String>,
+    retry: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    max_image_size: Option<u64>,
+    dedupe: Option<Arc<dyn DedupeCache>>,
+    ledger: Option<Arc<dyn UploadLedger>>,
 }
 
 impl ImgBB {
@@ -94,6 +190,12 @@ impl ImgBB {
                 .build()
                 .unwrap(),
             api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retry: RetryPolicy::disabled(),
+            rate_limiter: None,
+            max_image_size: DEFAULT_MAX_IMAGE_SIZE,
+            dedupe: None,
+            ledger: None,
         }
     }
 
@@ -122,6 +224,12 @@ impl ImgBB {
             timeout: None,
             user_agent: None,
             client: None,
+            base_url: None,
+            retry: None,
+            rate_limiter: None,
+            max_image_size: None,
+            dedupe: None,
+            ledger: None,
         }
     }
 
@@ -153,11 +261,17 @@ impl ImgBB {
         Self {
             client,
             api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retry: RetryPolicy::disabled(),
+            rate_limiter: None,
+            max_image_size: DEFAULT_MAX_IMAGE_SIZE,
+            dedupe: None,
+            ledger: None,
         }
     }
 
     /// Read base64 data and return an [Uploader](Uploader) struct to upload in the next step
-    pub fn read_base64<T>(&self, data: T) -> Uploader
+    pub fn read_base64<T>(&self, data: T) -> Uploader<'_>
     where
         T: AsRef<str>,
     {
@@ -165,36 +279,74 @@ impl ImgBB {
             api_key: self.api_key.clone(),
             data: Some(data.as_ref().to_string()),
             expiration: None,
+            base_url: self.base_url.clone(),
+            transport: Transport::default(),
+            retry: self.retry.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            max_image_size: self.max_image_size,
+            dedupe: self.dedupe.clone(),
+            ledger: self.ledger.clone(),
+            source_path: None,
+            oversized: None,
             client: &self.client,
         }
     }
 
     /// Read bytes data and return an [Uploader](Uploader) struct to upload in the next step
-    pub fn read_bytes<T>(&self, data: T) -> Uploader
+    ///
+    /// Data over the client's size limit is not encoded, and
+    /// [uploading](Uploader::upload) it fails with
+    /// [Error::ImageTooLarge](Error::ImageTooLarge).
+    pub fn read_bytes<T>(&self, data: T) -> Uploader<'_>
     where
         T: AsRef<[u8]>,
     {
-        let d = general_purpose::STANDARD.encode(data.as_ref());
+        let size = data.as_ref().len() as u64;
+        let oversized = (size > self.max_image_size).then_some(size);
+        let d = match oversized {
+            Some(_) => None,
+            None => Some(general_purpose::STANDARD.encode(data.as_ref())),
+        };
+
         Uploader {
             api_key: self.api_key.clone(),
-            data: Some(d),
+            data: d,
             expiration: None,
+            base_url: self.base_url.clone(),
+            transport: Transport::default(),
+            retry: self.retry.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            max_image_size: self.max_image_size,
+            dedupe: self.dedupe.clone(),
+            ledger: self.ledger.clone(),
+            source_path: None,
+            oversized,
             client: &self.client,
         }
     }
 
     /// Read file from path and return an [Uploader](Uploader) struct to upload in the next step
-    pub fn read_file<P>(&self, path: P) -> Result<Uploader, Error>
+    pub fn read_file<P>(&self, path: P) -> Result<Uploader<'_>, Error>
     where
         P: AsRef<Path>,
     {
-        let f = std::fs::read(path)?;
+        check_size(std::fs::metadata(path.as_ref())?.len(), self.max_image_size)?;
+        let f = std::fs::read(path.as_ref())?;
         let d = Some(general_purpose::STANDARD.encode(f));
 
         Ok(Uploader {
             api_key: self.api_key.clone(),
             data: d,
             expiration: None,
+            base_url: self.base_url.clone(),
+            transport: Transport::default(),
+            retry: self.retry.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            max_image_size: self.max_image_size,
+            dedupe: self.dedupe.clone(),
+            ledger: self.ledger.clone(),
+            source_path: Some(path.as_ref().to_path_buf()),
+            oversized: None,
             client: &self.client,
         })
     }
@@ -203,11 +355,12 @@ impl ImgBB {
     pub fn upload_builder(&self) -> UploaderBuilder {
         UploaderBuilder {
             api_key: self.api_key.clone(),
-            data: None,
-            expiration: None,
-            name: None,
-            title: None,
-            album: None,
+            options: UploadOptions::new(self.max_image_size),
+            base_url: self.base_url.clone(),
+            retry: self.retry.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            dedupe: self.dedupe.clone(),
+            ledger: self.ledger.clone(),
             client: self.client.clone(),
         }
     }
@@ -229,71 +382,218 @@ impl ImgBB {
         T: Into<String>,
     {
         let query = [("key", self.api_key.as_str())];
-        let res = self.client
-            .delete(&delete_url.into())
-            .query(&query)
-            .send()
-            .await?;
-
-        let status = res.status();
-        let body = res.text().await?;
-
-        // Try to parse the response
-        match serde_json::from_str::<Response>(&body) {
-            Ok(response) => {
-                if let Some(error) = response.error {
-                    let error_code = error.code.unwrap_or(0);
-                    let error_message = error.message.unwrap_or_else(|| "Unknown error".to_string());
-                    
-                    return match error_code {
-                        100 => Err(Error::InvalidApiKey),
-                        400 => Err(Error::InvalidParameters(error_message)),
-                        429 => Err(Error::RateLimitExceeded),
-                        _ => Err(Error::ApiError {
-                            message: error_message,
-                            status: Some(status.as_u16()),
-                            code: Some(error_code),
-                        }),
+        let delete_url = delete_url.into();
+
+        retry::execute(
+            &self.retry,
+            self.rate_limiter.as_ref(),
+            || Ok(self.client.delete(&delete_url).query(&query)),
+            parse_delete_response,
+        )
+        .await
+    }
+
+    /// Ledger the client records its uploads in, if one was set with
+    /// [ImgBBBuilder::ledger](ImgBBBuilder::ledger)
+    pub fn ledger(&self) -> Option<&dyn UploadLedger> {
+        self.ledger.as_deref()
+    }
+
+    /// Delete recorded images from ImgBB and remove them from the ledger
+    ///
+    /// Images are deleted one after the other using their recorded delete
+    /// URL. Entries whose image was deleted are removed from the client's
+    /// ledger, if it has one, and their response from its dedupe cache, so
+    /// the same bytes are uploaded again. Results are returned in input order.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - Ledger entries of the images to delete
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ledger::JsonLinesLedger;
+    /// use imgbb::{ImgBB, UploadLedger};
+    /// use std::path::Path;
+    ///
+    /// async fn example() -> Result<(), imgbb::Error> {
+    ///     let imgbb = ImgBB::builder("your_api_key")
+    ///         .ledger(JsonLinesLedger::open("uploads.jsonl"))
+    ///         .build()?;
+    ///
+    ///     // Delete every upload of a file
+    ///     let ledger = imgbb.ledger().unwrap();
+    ///     let entries = ledger.find_by_source(Path::new("path/to/image.jpg"))?;
+    ///     for result in imgbb.delete_recorded(entries).await {
+    ///         result?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Each result is an error if:
+    /// - The entry has no delete URL, as
+    ///   [Error::InvalidParameters](Error::InvalidParameters)
+    /// - The delete request fails
+    /// - The entry cannot be removed from the ledger
+    pub async fn delete_recorded<I>(&self, entries: I) -> Vec<Result<(), Error>>
+    where
+        I: IntoIterator<Item = LedgerEntry>,
+    {
+        let mut results = Vec::new();
+        for entry in entries {
+            let result = match ledger::delete_url(&entry) {
+                Ok(delete_url) => self.delete(delete_url).await,
+                Err(e) => Err(e),
+            };
+            results.push(result.and_then(|()| {
+                let forgotten = ledger::forget(self.ledger(), &entry);
+                dedupe::evict(self.dedupe.as_deref(), entry.hash.as_ref());
+                forgotten
+            }));
+        }
+        results
+    }
+
+    /// Reconcile the client's ledger with the images on ImgBB
+    ///
+    /// Entries whose expiration has passed are marked as
+    /// [EntryStatus::Expired]. Depending on the policy, images past the
+    /// retention period are deleted and removed from the ledger, and the
+    /// remaining images are checked with a `HEAD` request, marking the ones
+    /// that no longer exist as [EntryStatus::Missing]. Deleted and missing
+    /// images are also removed from the client's dedupe cache. Entries are
+    /// handled one after the other, and the deleted ones are removed from
+    /// the ledger together at the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - What to do besides marking expired entries
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ledger::JsonLinesLedger;
+    /// use imgbb::{ImgBB, SweepPolicy};
+    /// use std::time::Duration;
+    ///
+    /// async fn example() -> Result<(), imgbb::Error> {
+    ///     let imgbb = ImgBB::builder("your_api_key")
+    ///         .ledger(JsonLinesLedger::open("uploads.jsonl"))
+    ///         .build()?;
+    ///
+    ///     let policy = SweepPolicy::new()
+    ///         .retention(Duration::from_secs(7 * 24 * 60 * 60))
+    ///         .check_remote(true);
+    ///     let report = imgbb.sweep(&policy).await?;
+    ///
+    ///     println!("{} expired, {} missing", report.expired.len(), report.missing.len());
+    ///     for entry in report.deleted {
+    ///         println!("Deleted {}", entry.id);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The client has no ledger, as
+    ///   [Error::InvalidParameters](Error::InvalidParameters)
+    /// - The ledger cannot be read
+    ///
+    /// Failed deletions, checks and ledger updates are listed in
+    /// [SweepReport::failures](SweepReport::failures) instead, so images
+    /// already deleted are always reported.
+    pub async fn sweep(&self, policy: &SweepPolicy) -> Result<SweepReport, Error> {
+        let ledger = sweep::require_ledger(self.ledger())?;
+        let now = std::time::SystemTime::now();
+        let mut report = SweepReport::default();
+
+        for entry in ledger.entries()? {
+            match sweep::plan(&entry, policy, now) {
+                Step::Skip => {}
+                Step::MarkExpired => report.mark(ledger, entry, EntryStatus::Expired),
+                Step::Delete => {
+                    let result = match ledger::delete_url(&entry) {
+                        Ok(delete_url) => self.delete(delete_url).await,
+                        Err(e) => Err(e),
                     };
+                    report.deleted(self.dedupe.as_deref(), entry, result);
                 }
-                Ok(())
-            },
-            Err(_) => {
-                if status.is_success() {
-                    Ok(())
-                } else {
-                    Err(Error::ApiError {
-                        message: format!("Delete failed: {}", body),
-                        status: Some(status.as_u16()),
-                        code: None,
-                    })
+                Step::Check => {
+                    let result = match sweep::check_url(&entry) {
+                        Ok(url) => {
+                            retry::execute(
+                                &self.retry,
+                                self.rate_limiter.as_ref(),
+                                || Ok(self.client.head(url)),
+                                parse_check_response,
+                            )
+                            .await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    report.checked(ledger, self.dedupe.as_deref(), entry, result);
                 }
             }
         }
+
+        report.forget_deleted(ledger);
+        Ok(report)
     }
 
     /// Straightforward upload base64 data to ImgBB
-    pub async fn upload_base64<T>(&self, data: T) -> Result<Response, Error>
+    pub async fn upload_base64<T>(&self, data: T) -> Result<UploadedImage, Error>
     where
         T: AsRef<str>,
     {
-        self.read_base64(data).upload().await
+        self.read_base64(data).upload().await?.try_into()
     }
 
     /// Straightforward upload bytes data to ImgBB
-    pub async fn upload_bytes<T>(&self, data: T) -> Result<Response, Error>
+    pub async fn upload_bytes<T>(&self, data: T) -> Result<UploadedImage, Error>
     where
         T: AsRef<[u8]>,
     {
-        self.read_bytes(data).upload().await
+        self.read_bytes(data).upload().await?.try_into()
     }
 
     /// Straightforward upload file to ImgBB
-    pub async fn upload_file<P>(&self, path: P) -> Result<Response, Error>
+    pub async fn upload_file<P>(&self, path: P) -> Result<UploadedImage, Error>
     where
         P: AsRef<Path>,
     {
-        self.read_file(path)?.upload().await
+        self.read_file(path)?.upload().await?.try_into()
+    }
+
+    /// Straightforward upload an image from a remote http(s) URL to ImgBB
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ImgBB;
+    ///
+    /// async fn example() -> Result<(), imgbb::Error> {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///
+    ///     let image = imgbb.upload_url("https://example.com/image.png").await?;
+    ///
+    ///     println!("Image URL: {}", image.url);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn upload_url<T>(&self, url: T) -> Result<UploadedImage, Error>
+    where
+        T: AsRef<str>,
+    {
+        self.upload_builder().url(url)?.upload().await
     }
 
     /// Upload base64 data to ImgBB with expiration time (seconds)
@@ -301,13 +601,13 @@ impl ImgBB {
         &self,
         data: T,
         expiration: u64,
-    ) -> Result<Response, Error>
+    ) -> Result<UploadedImage, Error>
     where
         T: AsRef<str>,
     {
         let mut uploader = self.read_base64(data);
         uploader.expiration(expiration);
-        uploader.upload().await
+        uploader.upload().await?.try_into()
     }
 
     /// Upload bytes data to ImgBB with expiration time (seconds)
@@ -315,13 +615,13 @@ impl ImgBB {
         &self,
         data: T,
         expiration: u64,
-    ) -> Result<Response, Error>
+    ) -> Result<UploadedImage, Error>
     where
         T: AsRef<[u8]>,
     {
         let mut uploader = self.read_bytes(data);
         uploader.expiration(expiration);
-        uploader.upload().await
+        uploader.upload().await?.try_into()
     }
 
     /// Upload file to ImgBB with expiration time (seconds)
@@ -329,13 +629,148 @@ impl ImgBB {
         &self,
         path: P,
         expiration: u64,
-    ) -> Result<Response, Error>
+    ) -> Result<UploadedImage, Error>
     where
         P: AsRef<Path>,
     {
         let mut uploader = self.read_file(path)?;
         uploader.expiration(expiration);
-        uploader.upload().await
+        uploader.upload().await?.try_into()
+    }
+
+    /// Upload many images with at most `concurrency` uploads in flight
+    ///
+    /// Every item is uploaded through an [UploaderBuilder](UploaderBuilder)
+    /// of this client, so retries, rate limiting and every other option
+    /// behave exactly as for single uploads. Results are returned in input
+    /// order. A `concurrency` of 0 is treated as 1.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::{ImgBB, UploadSpec};
+    ///
+    /// async fn example() {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///
+    ///     let specs = (1..=3).map(|i| UploadSpec::file(format!("image_{}.png", i)));
+    ///     let results = imgbb.upload_batch(specs, 2).await;
+    ///
+    ///     let failed = results.iter().filter(|result| result.is_err()).count();
+    ///     println!("{} of {} uploads failed", failed, results.len());
+    /// }
+    /// ```
+    pub async fn upload_batch<I>(&self, items: I, concurrency: usize) -> Vec<Result<UploadedImage, Error>>
+    where
+        I: IntoIterator<Item = UploadSpec>,
+    {
+        stream::iter(items)
+            .map(|spec| self.upload_spec(spec))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Upload many images, yielding each result as soon as it finishes
+    ///
+    /// Like [upload_batch](ImgBB::upload_batch), but results arrive in
+    /// completion order, paired with the index of their item in the input.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use futures_util::StreamExt;
+    /// use imgbb::{ImgBB, UploadSpec};
+    ///
+    /// async fn example() {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///
+    ///     let specs = vec![UploadSpec::file("a.png"), UploadSpec::file("b.png")];
+    ///     let mut results = imgbb.upload_batch_stream(specs, 2);
+    ///
+    ///     while let Some((index, result)) = results.next().await {
+    ///         println!("Item {} finished: {}", index, result.is_ok());
+    ///     }
+    /// }
+    /// ```
+    pub fn upload_batch_stream<'a, I>(
+        &'a self,
+        items: I,
+        concurrency: usize,
+    ) -> impl Stream<Item = (usize, Result<UploadedImage, Error>)> + 'a
+    where
+        I: IntoIterator<Item = UploadSpec>,
+        I::IntoIter: 'a,
+    {
+        stream::iter(items.into_iter().enumerate())
+            .map(move |(index, spec)| async move { (index, self.upload_spec(spec).await) })
+            .buffer_unordered(concurrency.max(1))
+    }
+
+    /// Upload many images, reporting the aggregate progress of the batch
+    ///
+    /// Like [upload_batch](ImgBB::upload_batch), but `on_progress` receives
+    /// a [BatchProgress](BatchProgress) whenever any item makes progress or
+    /// finishes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::{ImgBB, UploadSpec};
+    ///
+    /// async fn example() {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///
+    ///     let specs = vec![UploadSpec::file("a.png"), UploadSpec::file("b.png")];
+    ///     let results = imgbb
+    ///         .upload_batch_with_progress(specs, 2, |progress| {
+    ///             println!(
+    ///                 "{}/{} done, {} bytes sent",
+    ///                 progress.items_completed, progress.items_total, progress.bytes_sent
+    ///             );
+    ///         })
+    ///         .await;
+    /// }
+    /// ```
+    pub async fn upload_batch_with_progress<I, F>(
+        &self,
+        items: I,
+        concurrency: usize,
+        on_progress: F,
+    ) -> Vec<Result<UploadedImage, Error>>
+    where
+        I: IntoIterator<Item = UploadSpec>,
+        F: Fn(BatchProgress) + Send + Sync + 'static,
+    {
+        let items = items.into_iter().collect::<Vec<_>>();
+        let tracker = Arc::new(BatchTracker::new(items.len(), on_progress));
+
+        stream::iter(items.into_iter().enumerate())
+            .map(|(index, spec)| {
+                let tracker = tracker.clone();
+                async move {
+                    let result = match spec.into_builder(self.upload_builder()) {
+                        Ok(builder) => {
+                            let item_tracker = tracker.clone();
+                            builder
+                                .on_progress(move |progress| item_tracker.item(index, progress))
+                                .upload()
+                                .await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    tracker.finished(result.is_ok());
+                    result
+                }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Upload a single batch item
+    async fn upload_spec(&self, spec: UploadSpec) -> Result<UploadedImage, Error> {
+        spec.into_builder(self.upload_builder())?.upload().await
     }
 }
 
@@ -423,6 +858,176 @@ impl ImgBBBuilder {
         self
     }
 
+    /// Set a custom base URL for the ImgBB API
+    ///
+    /// Every upload made through the built client, its [Uploader](Uploader)s and
+    /// [UploaderBuilder](UploaderBuilder)s is sent to `{base_url}/upload`.
+    /// Defaults to [DEFAULT_BASE_URL](DEFAULT_BASE_URL). Useful for staging
+    /// proxies, egress gateways or local mock servers.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base URL of the API, without the `/upload` path
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ImgBB;
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .base_url("http://localhost:8080/1")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn base_url<T>(mut self, base_url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Set the retry policy for uploads and deletes
+    ///
+    /// By default, failed requests are not retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry` - The retry policy to apply to every request
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::{ImgBB, RetryPolicy};
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .retry(RetryPolicy::new().max_attempts(5))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Set a rate limiter shared by all requests of the client
+    ///
+    /// Uploads and deletes made by the client and its uploaders wait for a
+    /// token before they are sent. By default, requests are not limited.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate_limiter` - The rate limiter to wait on before each request
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::{ImgBB, RateLimiter};
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .rate_limiter(RateLimiter::per_minute(60).burst(10))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Set the largest raw image size, in bytes, that uploads accept
+    ///
+    /// Larger images fail with [Error::ImageTooLarge](Error::ImageTooLarge)
+    /// before they are encoded or sent. Files are checked using their
+    /// metadata, before they are read. Defaults to [DEFAULT_MAX_IMAGE_SIZE].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_image_size` - Size limit in bytes
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ImgBB;
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .max_image_size(10 * 1024 * 1024)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn max_image_size(mut self, max_image_size: u64) -> Self {
+        self.max_image_size = Some(max_image_size);
+        self
+    }
+
+    /// Skip uploads of images whose bytes were uploaded before
+    ///
+    /// Uploads of files, bytes and base64 data are looked up in the cache
+    /// by the SHA-256 hash of the bytes that would be sent. On a hit, the
+    /// stored response is returned without calling the API, unless the
+    /// image has expired. Successful uploads are added to the cache. See
+    /// [DedupeCache] for the available caches.
+    ///
+    /// Metadata stripping and, with the `image-processing` feature,
+    /// shrinking run before the hash is taken, so a cache hit still pays for
+    /// them.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The cache shared by all uploads of the client
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::dedupe::JsonFileCache;
+    /// use imgbb::ImgBB;
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .dedupe_cache(JsonFileCache::open("uploads.json").unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn dedupe_cache<C>(mut self, cache: C) -> Self
+    where
+        C: DedupeCache + 'static,
+    {
+        self.dedupe = Some(Arc::new(cache));
+        self
+    }
+
+    /// Record every successful upload in a ledger
+    ///
+    /// Entries hold the delete URL of the image along with its hash, source
+    /// file, name, title, album and expiration, so images can be found and
+    /// deleted later with [ImgBB::delete_recorded](ImgBB::delete_recorded).
+    /// See [UploadLedger] for the available ledgers.
+    ///
+    /// An upload that cannot be recorded fails with
+    /// [Error::Ledger](Error::Ledger), which still holds the response and
+    /// its delete URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `ledger` - The ledger shared by all uploads of the client
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ledger::JsonLinesLedger;
+    /// use imgbb::ImgBB;
+    ///
+    /// let imgbb = ImgBB::builder("your_api_key")
+    ///     .ledger(JsonLinesLedger::open("uploads.jsonl"))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn ledger<L>(mut self, ledger: L) -> Self
+    where
+        L: UploadLedger + 'static,
+    {
+        self.ledger = Some(Arc::new(ledger));
+        self
+    }
+
     /// Build the ImgBB client
     ///
     /// This method builds the ImgBB client with the configured options.
@@ -443,11 +1048,23 @@ impl ImgBBBuilder {
     ///
     /// Returns an error if the reqwest client builder fails to build.
     pub fn build(self) -> Result<ImgBB, Error> {
+        let base_url = self
+            .base_url
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        let retry = self.retry.unwrap_or_else(RetryPolicy::disabled);
+        let max_image_size = self.max_image_size.unwrap_or(DEFAULT_MAX_IMAGE_SIZE);
+
         // If a custom client was provided, use it
         if let Some(client) = self.client {
             return Ok(ImgBB {
                 client,
                 api_key: self.api_key,
+                base_url,
+                retry,
+                rate_limiter: self.rate_limiter,
+                max_image_size,
+                dedupe: self.dedupe,
+                ledger: self.ledger,
             });
         }
 
@@ -472,6 +1089,12 @@ impl ImgBBBuilder {
         Ok(ImgBB {
             client,
             api_key: self.api_key,
+            base_url,
+            retry,
+            rate_limiter: self.rate_limiter,
+            max_image_size,
+            dedupe: self.dedupe,
+            ledger: self.ledger,
         })
     }
 }
@@ -490,7 +1113,7 @@ impl ImgBBBuilder {
 ///     let imgbb = ImgBB::new("your_api_key");
 ///
 ///     // Create an uploader with custom options
-///     let response = imgbb.upload_builder()
+///     let image = imgbb.upload_builder()
 ///         .file("path/to/image.jpg")?
 ///         .name("custom_name")
 ///         .title("My Image")
@@ -499,7 +1122,7 @@ impl ImgBBBuilder {
 ///         .upload()
 ///         .await?;
 ///
-///     println!("Upload successful: {}", response.data.unwrap().url.unwrap());
+///     println!("Upload successful: {}", image.url);
 ///
 ///     Ok(())
 /// }
@@ -507,11 +1130,12 @@ impl ImgBBBuilder {
 #[derive(Clone)]
 pub struct UploaderBuilder {
     api_key: String,
-    data: Option<String>,
-    expiration: Option<u64>,
-    name: Option<String>,
-    title: Option<String>,
-    album: Option<String>,
+    options: UploadOptions,
+    base_url: String,
+    retry: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    dedupe: Option<Arc<dyn DedupeCache>>,
+    ledger: Option<Arc<dyn UploadLedger>>,
     client: reqwest::Client,
 }
 
@@ -525,11 +1149,14 @@ impl UploaderBuilder {
     where
         T: AsRef<str>,
     {
-        self.data = Some(data.as_ref().to_owned());
+        self.options.set_data(ImageSource::Base64(data.as_ref().to_owned()));
         self
     }
 
-    /// Set the raw bytes data for upload, which will be encoded as base64
+    /// Set the raw bytes data for upload
+    ///
+    /// The bytes are base64 encoded or sent as a multipart file part,
+    /// depending on the [transport](UploaderBuilder::transport).
     ///
     /// # Arguments
     ///
@@ -538,12 +1165,19 @@ impl UploaderBuilder {
     where
         T: AsRef<[u8]>,
     {
-        self.data = Some(general_purpose::STANDARD.encode(data.as_ref()));
+        self.options.set_data(ImageSource::Bytes {
+            data: Bytes::copy_from_slice(data.as_ref()),
+            filename: None,
+        });
         self
     }
 
     /// Set data from a file path
     ///
+    /// Files over the client's size limit are not read. With the
+    /// `image-processing` feature, they are read when the upload is sent if
+    /// `fit_within` will shrink them, and rejected otherwise.
+    ///
     /// # Arguments
     ///
     /// * `path` - Path to the image file
@@ -555,31 +1189,148 @@ impl UploaderBuilder {
     where
         P: AsRef<Path>,
     {
-        let f = std::fs::read(path)?;
-        self.data = Some(general_purpose::STANDARD.encode(f));
+        self.options.set_file(path.as_ref())?;
         Ok(self)
     }
 
-    /// Set the expiration time in seconds
+    /// Set a remote image URL for upload
+    ///
+    /// The URL is sent to ImgBB as-is, which then fetches the image itself.
     ///
     /// # Arguments
     ///
-    /// * `expiration` - Time in seconds until the image expires
-    pub fn expiration(mut self, expiration: u64) -> Self {
-        self.expiration = Some(expiration);
-        self
+    /// * `url` - http(s) URL of the image
+    ///
+    /// # Errors
+    ///
+    /// Returns [Error::InvalidParameters](Error::InvalidParameters) if the URL
+    /// cannot be parsed or does not use the http or https scheme
+    pub fn url<T>(mut self, url: T) -> Result<Self, Error>
+    where
+        T: AsRef<str>,
+    {
+        self.options.set_data(ImageSource::url(url.as_ref())?);
+        Ok(self)
     }
 
-    /// Set the image name
+    /// Set an async reader as the image source
+    ///
+    /// The reader is streamed into the request body while it is sent, so
+    /// the image is never held in memory as a whole. A stream can only be
+    /// sent once, so it is never retried, and uploading a clone of this
+    /// builder after the original fails with
+    /// [Error::InvalidParameters](Error::InvalidParameters).
     ///
     /// # Arguments
     ///
-    /// * `name` - Name for the uploaded image
+    /// * `reader` - Reader producing the raw image bytes
+    pub fn reader<R>(mut self, reader: R) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let stream = ReaderStream::with_capacity(reader, STREAM_CHUNK_SIZE);
+        self.options.set_data(ImageSource::Stream(StreamSource::new(
+            Box::pin(stream),
+            None,
+            None,
+        )));
+        self
+    }
+
+    /// Set an open file as the image source
+    ///
+    /// Like [reader](UploaderBuilder::reader), but the file size from its
+    /// metadata is sent as the length of the image.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Open file containing the raw image bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file metadata cannot be read
+    pub async fn file_handle(mut self, file: tokio::fs::File) -> Result<Self, Error> {
+        let length = file.metadata().await?.len();
+        check_size(length, self.options.max_size)?;
+        let stream = ReaderStream::with_capacity(file, STREAM_CHUNK_SIZE);
+        self.options.set_data(ImageSource::Stream(StreamSource::new(
+            Box::pin(stream),
+            Some(length),
+            None,
+        )));
+        Ok(self)
+    }
+
+    /// Open a file and stream it as the image source
+    ///
+    /// Unlike [file](UploaderBuilder::file), the file is not read into
+    /// memory up front.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the image file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened
+    pub async fn file_streamed<P>(self, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = tokio::fs::File::open(path.as_ref()).await?;
+        let mut builder = self.file_handle(file).await?;
+
+        if let Some(ImageSource::Stream(source)) = &mut builder.options.data {
+            source.filename = file_name(path.as_ref());
+        }
+        builder.options.source_path = Some(path.as_ref().to_path_buf());
+
+        Ok(builder)
+    }
+
+    /// Set a stream of byte chunks as the image source
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - Stream producing the raw image bytes
+    pub fn stream<S>(mut self, stream: S) -> Self
+    where
+        S: Stream<Item = Bytes> + Send + 'static,
+    {
+        self.options.set_data(ImageSource::Stream(StreamSource::new(
+            Box::pin(stream.map(Ok)),
+            None,
+            None,
+        )));
+        self
+    }
+
+    /// Set the expiration time
+    ///
+    /// The expiration is checked against the range accepted by ImgBB when
+    /// the upload is sent, see [Expiration].
+    ///
+    /// # Arguments
+    ///
+    /// * `expiration` - Seconds or [Duration] until the image expires
+    pub fn expiration<T>(mut self, expiration: T) -> Self
+    where
+        T: Into<Expiration>,
+    {
+        self.options.expiration = Some(expiration.into());
+        self
+    }
+
+    /// Set the image name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name for the uploaded image
     pub fn name<T>(mut self, name: T) -> Self
     where
         T: Into<String>,
     {
-        self.name = Some(name.into());
+        self.options.name = Some(name.into());
         self
     }
 
@@ -592,7 +1343,7 @@ impl UploaderBuilder {
     where
         T: Into<String>,
     {
-        self.title = Some(title.into());
+        self.options.title = Some(title.into());
         self
     }
 
@@ -605,80 +1356,896 @@ impl UploaderBuilder {
     where
         T: Into<String>,
     {
-        self.album = Some(album.into());
+        self.options.album = Some(album.into());
+        self
+    }
+
+    /// Set how the image data is sent
+    ///
+    /// Defaults to [Transport::Auto](Transport::Auto), which uses multipart for
+    /// large inputs and form encoding otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The transport to use for this upload
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.options.transport = transport;
+        self
+    }
+
+    /// Call a function whenever the upload makes progress
+    ///
+    /// The callback runs on the task sending the request, so it should
+    /// return quickly. Several callbacks can be registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - Function receiving a [Progress](Progress) snapshot
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ImgBB;
+    ///
+    /// async fn example() -> Result<(), imgbb::Error> {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///
+    ///     let image = imgbb.upload_builder()
+    ///         .file("path/to/image.jpg")?
+    ///         .on_progress(|progress| {
+    ///             if let Some(fraction) = progress.fraction() {
+    ///                 println!("{:.0}% sent", fraction * 100.0);
+    ///             }
+    ///         })
+    ///         .upload()
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        self.options.progress.push(callback);
+        self
+    }
+
+    /// Publish upload progress to a `watch` channel
+    ///
+    /// # Arguments
+    ///
+    /// * `sender` - Sender updated with the latest [Progress](Progress)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::{ImgBB, Progress};
+    /// use tokio::sync::watch;
+    ///
+    /// async fn example() -> Result<(), imgbb::Error> {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///     let (sender, mut receiver) = watch::channel(Progress::default());
+    ///
+    ///     tokio::spawn(async move {
+    ///         while receiver.changed().await.is_ok() {
+    ///             println!("{} bytes sent", receiver.borrow().bytes_sent);
+    ///         }
+    ///     });
+    ///
+    ///     imgbb.upload_builder()
+    ///         .file("path/to/image.jpg")?
+    ///         .progress_watch(sender)
+    ///         .upload()
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn progress_watch(self, sender: tokio::sync::watch::Sender<Progress>) -> Self {
+        self.on_progress(move |progress| {
+            sender.send_replace(progress);
+        })
+    }
+
+    /// Shrink the image before uploading so it fits the given limits
+    ///
+    /// The image is decoded, scaled down to `max_dimension` and re-encoded,
+    /// with lower JPEG qualities or as WebP, until it is at most `max_bytes`
+    /// long. The EXIF orientation is applied to the pixels and the ICC
+    /// profile is kept. Images that already fit are sent unchanged. Limits
+    /// of zero are rejected when uploading. Use
+    /// [upload_processed](UploaderBuilder::upload_processed) to find out
+    /// what was changed. Streams and URLs are sent unchanged.
+    ///
+    /// Files over the client's size limit are accepted, whether the policy
+    /// is set before or after [file](UploaderBuilder::file). With a
+    /// [DedupeCache], images are hashed after they are shrunk, so a cache
+    /// hit does not avoid the cost of decoding and re-encoding them.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - Largest encoded size in bytes
+    /// * `max_dimension` - Largest width or height in pixels
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ImgBB;
+    ///
+    /// async fn example() -> Result<(), imgbb::Error> {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///
+    ///     let upload = imgbb.upload_builder()
+    ///         .fit_within(5 * 1024 * 1024, 4096)
+    ///         .file("path/to/huge_photo.jpg")?
+    ///         .upload_processed()
+    ///         .await?;
+    ///
+    ///     if let Some(transformation) = upload.transformation {
+    ///         println!(
+    ///             "Shrunk from {} to {} bytes",
+    ///             transformation.original_size, transformation.size
+    ///         );
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "image-processing")]
+    pub fn fit_within(mut self, max_bytes: u64, max_dimension: u32) -> Self {
+        self.options.fit = Some(FitPolicy {
+            max_bytes,
+            max_dimension,
+        });
         self
     }
 
+    /// Remove metadata from the image before uploading
+    ///
+    /// EXIF, XMP, IPTC and comment blocks are removed from JPEG, PNG, WebP
+    /// and GIF images without re-encoding the pixels, so GPS coordinates
+    /// and camera serial numbers are not published. Use
+    /// [upload_processed](UploaderBuilder::upload_processed) to find out
+    /// which blocks were removed.
+    ///
+    /// The EXIF orientation is kept, so photos are not displayed rotated,
+    /// and so are ICC profiles, unless
+    /// [strip_icc_profile](UploaderBuilder::strip_icc_profile) is set. See
+    /// [metadata::strip_metadata_with].
+    ///
+    /// Uploads of streams, URLs and images in other formats that may carry
+    /// metadata, such as TIFF or HEIC, fail rather than being sent with
+    /// their metadata. BMP images are sent unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use imgbb::ImgBB;
+    ///
+    /// async fn example() -> Result<(), imgbb::Error> {
+    ///     let imgbb = ImgBB::new("your_api_key");
+    ///
+    ///     let upload = imgbb.upload_builder()
+    ///         .strip_metadata(true)
+    ///         .file("path/to/photo.jpg")?
+    ///         .upload_processed()
+    ///         .await?;
+    ///
+    ///     if let Some(metadata) = upload.metadata {
+    ///         for block in metadata.removed {
+    ///             println!("Removed {} ({} bytes)", block.kind, block.size);
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn strip_metadata(mut self, strip: bool) -> Self {
+        self.options.strip_metadata = strip;
+        self
+    }
+
+    /// Also remove ICC color profiles when stripping metadata
+    ///
+    /// Images in a wide gamut color space, such as Display P3 photos from
+    /// phones, are displayed with shifted colors without their profile.
+    /// Has no effect unless [strip_metadata](UploaderBuilder::strip_metadata)
+    /// is set.
+    pub fn strip_icc_profile(mut self, strip: bool) -> Self {
+        self.options.strip_options = self.options.strip_options.remove_icc(strip);
+        self
+    }
+
+    /// Remove metadata and shrink the image as requested, then upload it
+    ///
+    /// Reports the changes made by
+    /// [strip_metadata](UploaderBuilder::strip_metadata) and, with the
+    /// `image-processing` feature, `fit_within`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [upload](UploaderBuilder::upload),
+    /// [Error::InvalidParameters](Error::InvalidParameters) if metadata
+    /// cannot be removed from the image, `Error::ImageProcessing` if the
+    /// image cannot be decoded or encoded, and `Error::ProcessingTask` if
+    /// processing panicked
+    pub async fn upload_processed(mut self) -> Result<ProcessedUpload, Error> {
+        self.options.check_before_processing()?;
+        let metadata = self.options.apply_strip()?;
+        #[cfg(feature = "image-processing")]
+        let transformation = self.process().await?;
+        let image = self.upload().await?;
+
+        Ok(ProcessedUpload {
+            image,
+            metadata,
+            #[cfg(feature = "image-processing")]
+            transformation,
+        })
+    }
+
+    /// Apply the fit policy on the blocking thread pool
+    #[cfg(feature = "image-processing")]
+    async fn process(&mut self) -> Result<Option<Transformation>, Error> {
+        if self.options.fit.is_none() {
+            return Ok(None);
+        }
+
+        let mut options = self.options.clone();
+        let (options, transformation) = tokio::task::spawn_blocking(move || {
+            let transformation = options.apply_fit()?;
+            Ok::<_, Error>((options, transformation))
+        })
+        .await
+        .map_err(|e| match e.try_into_panic() {
+            Ok(payload) => Error::ProcessingTask(processing::panic_message(&*payload)),
+            Err(e) => Error::ProcessingTask(e.to_string()),
+        })??;
+
+        self.options = options;
+        Ok(transformation)
+    }
+
+    /// Format of the image, detected from its leading bytes
+    ///
+    /// This is the MIME type used for multipart uploads. Returns `None` for
+    /// streams, URLs and images of unknown format.
+    pub fn format(&self) -> Option<ImageFormat> {
+        self.options.data.as_ref().and_then(ImageSource::format)
+    }
+
     /// Upload the image with all specified options
     ///
     /// # Errors
     ///
     /// Returns an error if:
     /// - No image data has been set
+    /// - The image is in a format ImgBB does not accept, as
+    ///   [Error::UnsupportedFormat](Error::UnsupportedFormat)
+    /// - The API request fails
+    /// - The API returns an error response
+    /// - The response lacks a required field, as
+    ///   [Error::MissingField](Error::MissingField)
+    pub async fn upload(self) -> Result<UploadedImage, Error> {
+        self.send().await?.try_into()
+    }
+
+    /// Upload the image and return the API response as-is
+    ///
+    /// Like [upload](UploaderBuilder::upload), but without checking that the
+    /// response contains every field of an [UploadedImage](UploadedImage).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - No image data has been set
     /// - The API request fails
     /// - The API returns an error response
-    pub async fn upload(self) -> Result<Response, Error> {
-        if self.data.is_none() {
-            return Err(Error::MissingField("data".to_string()));
+    pub async fn upload_response(self) -> Result<Response, Error> {
+        self.send().await
+    }
+
+    /// Send the upload request, or return the cached response of an
+    /// identical upload
+    async fn send(mut self) -> Result<Response, Error> {
+        self.options.check_before_processing()?;
+        self.options.apply_strip()?;
+        #[cfg(feature = "image-processing")]
+        self.process().await?;
+
+        self.options.check_image()?;
+        let query = self.options.query(&self.api_key)?;
+
+        let hash = (self.dedupe.is_some() || self.ledger.is_some())
+            .then(|| self.options.content_hash())
+            .flatten();
+        let cached = self.dedupe.clone().zip(hash);
+        if let Some((cache, hash)) = &cached {
+            if let Some(response) = dedupe::lookup(cache.as_ref(), hash) {
+                return Ok(response);
+            }
         }
 
-        let mut query = vec![("key", self.api_key.as_str())];
-        let mut form = vec![("image", self.data.as_ref().unwrap().as_str())];
+        let endpoint = upload_endpoint(&self.base_url);
+        let retry = self.options.retry_policy(&self.retry);
 
-        // Store expiration string to extend its lifetime
-        let expiration_str;
-        if let Some(exp) = &self.expiration {
-            expiration_str = exp.to_string();
-            query.push(("expiration", expiration_str.as_str()));
+        let response = retry::execute(
+            &retry,
+            self.rate_limiter.as_ref(),
+            || {
+                let request = self.client.post(&endpoint).query(&query);
+                Ok(transport::apply(request, self.options.body()?))
+            },
+            parse_upload_response,
+        )
+        .await?;
+
+        if let Some((cache, hash)) = cached {
+            dedupe::store(cache.as_ref(), hash, &response);
+        }
+        if let Some(ledger) = &self.ledger {
+            ledger::record(ledger.as_ref(), &response, self.options.provenance(hash))?;
         }
+        Ok(response)
+    }
+}
+
+/// Result of [UploaderBuilder::upload_processed](UploaderBuilder::upload_processed)
+#[derive(Debug, Clone)]
+pub struct ProcessedUpload {
+    /// The uploaded image
+    pub image: UploadedImage,
+    /// Metadata removed before uploading, or `None` if
+    /// [strip_metadata](UploaderBuilder::strip_metadata) was not set
+    pub metadata: Option<StrippedMetadata>,
+    /// How the image was changed before uploading, or `None` if it already
+    /// fit the policy
+    #[cfg(feature = "image-processing")]
+    pub transformation: Option<Transformation>,
+}
+
+/// Image payload sent in the `image` field of an upload
+#[derive(Clone, Debug)]
+pub(crate) enum ImageSource {
+    /// Base64 encoded image data
+    Base64(String),
+    /// Raw image bytes, encoded according to the chosen [Transport](Transport)
+    Bytes {
+        data: Bytes,
+        filename: Option<String>,
+    },
+    /// Raw image bytes read from a stream while the request is sent
+    Stream(StreamSource),
+    /// Remote image URL, sent as-is
+    Url(String),
+}
+
+/// A streamed image that can be consumed by a single upload
+///
+/// Clones share the stream, so only the first upload made from a cloned
+/// [UploaderBuilder](UploaderBuilder) can send it.
+#[derive(Clone)]
+pub(crate) struct StreamSource {
+    stream: Arc<Mutex<Option<ByteStream>>>,
+    length: Option<u64>,
+    filename: Option<String>,
+}
+
+impl StreamSource {
+    pub(crate) fn new(stream: ByteStream, length: Option<u64>, filename: Option<String>) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(Some(stream))),
+            length,
+            filename,
+        }
+    }
+
+    /// Take the stream out of the source
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream was already consumed by another upload
+    fn take(&self) -> Result<ByteStream, Error> {
+        self.stream
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .ok_or_else(|| {
+                Error::invalid_parameters("Image stream has already been consumed")
+            })
+    }
+}
+
+impl std::fmt::Debug for StreamSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamSource")
+            .field("length", &self.length)
+            .field("filename", &self.filename)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ImageSource {
+    /// Build a URL source, checking that it is an http(s) URL
+    pub(crate) fn url(url: &str) -> Result<Self, Error> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| Error::invalid_parameters(format!("Invalid image URL '{}': {}", url, e)))?;
+
+        match parsed.scheme() {
+            "http" | "https" => Ok(ImageSource::Url(url.to_string())),
+            scheme => Err(Error::invalid_parameters(format!(
+                "Unsupported image URL scheme '{}', expected http or https",
+                scheme
+            ))),
+        }
+    }
+
+    /// Build a bytes source from the contents of a file
+    ///
+    /// The size is checked against `max_size` before the file is read.
+    pub(crate) fn file(path: &Path, max_size: u64) -> Result<Self, Error> {
+        check_size(std::fs::metadata(path)?.len(), max_size)?;
+        let data = std::fs::read(path)?;
+
+        Ok(ImageSource::Bytes {
+            data: Bytes::from(data),
+            filename: file_name(path),
+        })
+    }
+
+    /// Format of the image, detected from its leading bytes
+    ///
+    /// Streams and URLs are not inspected.
+    pub(crate) fn format(&self) -> Option<ImageFormat> {
+        match self {
+            ImageSource::Base64(data) => ImageFormat::detect_base64(data),
+            ImageSource::Bytes { data, .. } => ImageFormat::detect(data),
+            ImageSource::Stream(_) | ImageSource::Url(_) => None,
+        }
+    }
+
+    /// Approximate raw size of the image, if known before sending
+    fn raw_len(&self) -> Option<usize> {
+        match self {
+            ImageSource::Base64(data) => Some(transport::decoded_len(data)),
+            ImageSource::Bytes { data, .. } => Some(data.len()),
+            ImageSource::Stream(source) => source.length.map(|len| len as usize),
+            ImageSource::Url(_) => None,
+        }
+    }
+}
+
+/// Reject an image of `size` raw bytes if it exceeds `max_size`
+pub(crate) fn check_size(size: u64, max_size: u64) -> Result<(), Error> {
+    if size > max_size {
+        return Err(Error::image_too_large(size, max_size));
+    }
+    Ok(())
+}
+
+/// File name component of a path, used to label multipart file parts
+pub(crate) fn file_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Upload options shared by the async and blocking uploader builders
+#[derive(Clone, Debug)]
+pub(crate) struct UploadOptions {
+    pub(crate) data: Option<ImageSource>,
+    /// Path of the file the data was read from
+    pub(crate) source_path: Option<PathBuf>,
+    pub(crate) max_size: u64,
+    pub(crate) expiration: Option<Expiration>,
+    pub(crate) name: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) transport: Transport,
+    pub(crate) progress: ProgressObservers,
+    pub(crate) strip_metadata: bool,
+    pub(crate) strip_options: StripOptions,
+    #[cfg(feature = "image-processing")]
+    pub(crate) fit: Option<FitPolicy>,
+    /// Path and size of a file over the size limit, read only if it will be
+    /// shrunk to fit
+    #[cfg(feature = "image-processing")]
+    pub(crate) oversized_file: Option<(PathBuf, u64)>,
+}
+
+impl UploadOptions {
+    /// Options without an image, accepting images up to `max_size` bytes
+    pub(crate) fn new(max_size: u64) -> Self {
+        Self {
+            data: None,
+            source_path: None,
+            max_size,
+            expiration: None,
+            name: None,
+            title: None,
+            album: None,
+            transport: Transport::default(),
+            progress: ProgressObservers::default(),
+            strip_metadata: false,
+            strip_options: StripOptions::default(),
+            #[cfg(feature = "image-processing")]
+            fit: None,
+            #[cfg(feature = "image-processing")]
+            oversized_file: None,
+        }
+    }
+
+    /// Replace the image, forgetting the path of any previous file
+    pub(crate) fn set_data(&mut self, data: ImageSource) {
+        self.data = Some(data);
+        self.source_path = None;
+        #[cfg(feature = "image-processing")]
+        {
+            self.oversized_file = None;
+        }
+    }
+
+    /// Set the image from a file
+    ///
+    /// Files over the size limit fail, unless they may be shrunk to fit a
+    /// [FitPolicy] set later, in which case reading them is deferred to
+    /// [read_oversized_file](UploadOptions::read_oversized_file).
+    pub(crate) fn set_file(&mut self, path: &Path) -> Result<(), Error> {
+        #[cfg(feature = "image-processing")]
+        {
+            let size = std::fs::metadata(path)?.len();
+            if size > self.max_size {
+                self.data = None;
+                self.oversized_file = Some((path.to_path_buf(), size));
+                self.source_path = Some(path.to_path_buf());
+                return Ok(());
+            }
+        }
+
+        self.set_data(ImageSource::file(path, self.max_size)?);
+        self.source_path = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Read a file set over the size limit, now that it is known whether it
+    /// will be shrunk
+    ///
+    /// # Errors
+    ///
+    /// Returns [ImageTooLarge](Error::ImageTooLarge) if no [FitPolicy] is
+    /// set, or an error if the file cannot be read
+    #[cfg(feature = "image-processing")]
+    fn read_oversized_file(&mut self) -> Result<(), Error> {
+        let Some((path, size)) = self.oversized_file.take() else {
+            return Ok(());
+        };
+
+        if self.fit.is_none() {
+            return Err(Error::image_too_large(size, self.max_size));
+        }
+
+        self.data = Some(ImageSource::file(&path, u64::MAX)?);
+        Ok(())
+    }
+
+    /// Reject images ImgBB would not accept before they are stripped or
+    /// processed, reading a file set over the size limit if it may be shrunk
+    ///
+    /// Images that may be shrunk to fit a [FitPolicy] are only checked after
+    /// processing, by [check_image](UploadOptions::check_image).
+    pub(crate) fn check_before_processing(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "image-processing")]
+        {
+            self.read_oversized_file()?;
+            if self.fit.is_some() {
+                return Ok(());
+            }
+        }
+        self.check_image()
+    }
+
+    /// Where the image came from, recorded in the ledger
+    pub(crate) fn provenance(&self, hash: Option<ContentHash>) -> Provenance {
+        Provenance {
+            hash,
+            source_path: self.source_path.clone(),
+            name: self.name.clone(),
+            title: self.title.clone(),
+            album: self.album.clone(),
+        }
+    }
+
+    /// Remove metadata from the image, if requested
+    ///
+    /// The request is cleared, so the image is only stripped once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image is a stream or URL, or metadata cannot
+    /// be removed from it
+    pub(crate) fn apply_strip(&mut self) -> Result<Option<StrippedMetadata>, Error> {
+        if !std::mem::take(&mut self.strip_metadata) {
+            return Ok(None);
+        }
+
+        let (data, filename) = match &self.data {
+            Some(ImageSource::Bytes { data, filename }) => (data.clone(), filename.clone()),
+            Some(ImageSource::Base64(data)) => match transport::decode(data) {
+                Some(data) => (data, None),
+                None => return Err(Error::invalid_parameters("Invalid base64 image data")),
+            },
+            Some(ImageSource::Stream(_) | ImageSource::Url(_)) => {
+                return Err(Error::invalid_parameters(
+                    "Metadata can only be stripped from files, bytes and base64 data",
+                ))
+            }
+            None => return Ok(None),
+        };
+
+        let (stripped, metadata) = metadata::strip_metadata_with(&data, self.strip_options)?;
+        if !metadata.removed.is_empty() {
+            self.data = Some(ImageSource::Bytes {
+                data: Bytes::from(stripped),
+                filename,
+            });
+        }
+
+        Ok(Some(metadata))
+    }
+
+    /// Hash of the image bytes, used to look up identical uploads
+    ///
+    /// Returns `None` for streams, URLs and invalid base64 data.
+    pub(crate) fn content_hash(&self) -> Option<ContentHash> {
+        match self.data.as_ref()? {
+            ImageSource::Bytes { data, .. } => Some(ContentHash::of(data)),
+            ImageSource::Base64(data) => transport::decode(data).map(|data| ContentHash::of(&data)),
+            ImageSource::Stream(_) | ImageSource::Url(_) => None,
+        }
+    }
+
+    /// Shrink the image to fit the [FitPolicy], if one is set
+    ///
+    /// The policy is cleared, so the image is only processed once. Streams,
+    /// URLs and invalid base64 data are sent unchanged.
+    #[cfg(feature = "image-processing")]
+    pub(crate) fn apply_fit(&mut self) -> Result<Option<Transformation>, Error> {
+        let Some(policy) = self.fit.take() else {
+            return Ok(None);
+        };
+
+        let (data, filename) = match &self.data {
+            Some(ImageSource::Bytes { data, filename }) => (data.clone(), filename.clone()),
+            Some(ImageSource::Base64(data)) => match transport::decode(data) {
+                Some(data) => (data, None),
+                None => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+
+        let Some((encoded, transformation)) = processing::fit(&data, &policy)? else {
+            return Ok(None);
+        };
+
+        let extension = transformation.format.extension();
+        self.data = Some(ImageSource::Bytes {
+            data: Bytes::from(encoded),
+            filename: filename.map(|name| {
+                Path::new(&name)
+                    .with_extension(extension)
+                    .to_string_lossy()
+                    .into_owned()
+            }),
+        });
+
+        Ok(Some(transformation))
+    }
+
+    /// Query parameters of the upload request
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expiration is out of range
+    pub(crate) fn query(&self, api_key: &str) -> Result<Vec<(&'static str, String)>, Error> {
+        let mut query = vec![("key", api_key.to_string())];
+
+        if let Some(expiration) = self.expiration {
+            query.push(("expiration", expiration.validate()?.to_string()));
+        }
+
+        Ok(query)
+    }
+
+    /// Reject images ImgBB would not accept, before they are encoded
+    ///
+    /// # Errors
+    ///
+    /// Returns [ImageTooLarge](Error::ImageTooLarge) if the image is known to
+    /// exceed the size limit, or [UnsupportedFormat](Error::UnsupportedFormat)
+    /// with the detected format. Images of unknown size or format are left
+    /// to the API.
+    pub(crate) fn check_image(&self) -> Result<(), Error> {
+        if let Some(size) = self.data.as_ref().and_then(ImageSource::raw_len) {
+            check_size(size as u64, self.max_size)?;
+        }
+
+        match self.data.as_ref().and_then(ImageSource::format) {
+            Some(format) if !format.is_supported() => Err(Error::UnsupportedFormat {
+                format: Some(format),
+                context: None,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Retry policy to use for this upload
+    ///
+    /// Streamed images cannot be sent twice, so they are never retried.
+    pub(crate) fn retry_policy(&self, retry: &RetryPolicy) -> RetryPolicy {
+        match self.data {
+            Some(ImageSource::Stream(_)) => RetryPolicy::disabled(),
+            _ => retry.clone(),
+        }
+    }
+
+    /// Body of the upload request
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no image data has been set, or if a streamed
+    /// image has already been consumed
+    pub(crate) fn body(&self) -> Result<RequestBody<'_>, Error> {
+        let source = self
+            .data
+            .as_ref()
+            .ok_or_else(|| Error::MissingField("data".to_string()))?;
+
+        let mut metadata = Vec::new();
 
         if let Some(name) = &self.name {
-            form.push(("name", name.as_str()));
+            metadata.push(("name", name.clone()));
         }
 
         if let Some(title) = &self.title {
-            form.push(("title", title.as_str()));
+            metadata.push(("title", title.clone()));
         }
 
         if let Some(album) = &self.album {
-            form.push(("album", album.as_str()));
-        }
-
-        let res = self.client
-            .post(IMGBB_API_URL)
-            .query(&query)
-            .form(&form)
-            .send()
-            .await?;
-
-        let status = res.status();
-        let body = res.text().await?;
-
-        // Try to parse the response
-        match serde_json::from_str::<Response>(&body) {
-            Ok(response) => {
-                if let Some(error) = response.error {
-                    let error_code = error.code.unwrap_or(0);
-                    let error_message = error.message.unwrap_or_else(|| "Unknown error".to_string());
-                    
-                    return match error_code {
-                        100 => Err(Error::InvalidApiKey),
-                        400 => Err(Error::InvalidParameters(error_message)),
-                        429 => Err(Error::RateLimitExceeded),
-                        _ => Err(Error::ApiError {
-                            message: error_message,
-                            status: Some(status.as_u16()),
-                            code: Some(error_code),
-                        }),
-                    };
-                }
-                Ok(response)
+            metadata.push(("album", album.clone()));
+        }
+
+        let progress = self.progress.tracker();
+
+        let multipart = match source {
+            ImageSource::Stream(_) => self.transport != Transport::Form,
+            _ => transport::use_multipart(self.transport, source.raw_len()),
+        };
+
+        if !multipart {
+            if let ImageSource::Stream(stream) = source {
+                return Ok(RequestBody::FormStream(transport::form_stream(
+                    transport::form_prefix(&metadata),
+                    stream.take()?,
+                    progress,
+                )));
+            }
+
+            if let Some(progress) = progress {
+                let image = match source {
+                    ImageSource::Bytes { data, .. } => {
+                        progress.encoded(data.len());
+                        transport::form_encode(data)
+                    }
+                    ImageSource::Base64(data) => {
+                        progress.encoded(transport::decoded_len(data));
+                        transport::form_escape(data)
+                    }
+                    ImageSource::Url(url) => transport::form_escape(url),
+                    ImageSource::Stream(_) => unreachable!("streams are handled above"),
+                };
+                progress.total(Some(image.len() as u64));
+
+                return Ok(RequestBody::FormStream(transport::counted_form(
+                    transport::form_prefix(&metadata),
+                    image,
+                    &progress,
+                )));
+            }
+
+            let image = match source {
+                ImageSource::Base64(data) | ImageSource::Url(data) => Cow::Borrowed(data.as_str()),
+                ImageSource::Bytes { data, .. } => Cow::Owned(transport::encode(data)),
+                ImageSource::Stream(_) => unreachable!("streams are handled above"),
+            };
+
+            let mut form = vec![("image", image)];
+            form.extend(metadata.into_iter().map(|(key, value)| (key, Cow::Owned(value))));
+            return Ok(RequestBody::Form(form));
+        }
+
+        let image = match source {
+            ImageSource::Url(url) => transport::Part::Text(url.clone()),
+            ImageSource::Base64(data) => match transport::decode(data) {
+                Some(data) => self.file_part(data, None, source.format()),
+                // Let the API report invalid base64 data
+                None => transport::Part::Text(data.clone()),
             },
-            Err(_) => Err(Error::ApiError {
-                message: format!("Failed to parse response: {}", body),
-                status: Some(status.as_u16()),
-                code: None,
-            }),
+            ImageSource::Bytes { data, filename } => {
+                self.file_part(data.clone(), filename.as_deref(), source.format())
+            }
+            ImageSource::Stream(stream) => {
+                let filename = self.part_filename(stream.filename.as_deref());
+                let mut body = stream.take()?;
+                if let Some(progress) = &progress {
+                    progress.total(stream.length);
+                    body = progress.count_raw(body);
+                }
+                transport::Part::Stream {
+                    stream: body,
+                    length: stream.length,
+                    mime: transport::mime_from_filename(&filename),
+                    filename,
+                }
+            }
+        };
+
+        // Stream in-memory file data in chunks so progress can be reported
+        let image = match (image, &progress) {
+            (
+                transport::Part::File {
+                    data,
+                    filename,
+                    mime,
+                },
+                Some(progress),
+            ) => {
+                progress.total(Some(data.len() as u64));
+                transport::Part::Stream {
+                    length: Some(data.len() as u64),
+                    stream: transport::counted_file(data, progress),
+                    filename,
+                    mime,
+                }
+            }
+            (image, _) => image,
+        };
+
+        let mut parts = vec![("image", image)];
+        parts.extend(
+            metadata
+                .into_iter()
+                .map(|(key, value)| (key, transport::Part::Text(value))),
+        );
+        Ok(RequestBody::Multipart(parts))
+    }
+
+    /// Build the multipart file part for raw image bytes
+    ///
+    /// The detected format takes precedence over the file extension when
+    /// labelling the part.
+    fn file_part(
+        &self,
+        data: Bytes,
+        filename: Option<&str>,
+        format: Option<ImageFormat>,
+    ) -> transport::Part {
+        let filename = self.part_filename(filename);
+        let mime = format
+            .map(|format| format.mime_type())
+            .unwrap_or_else(|| transport::mime_from_filename(&filename));
+
+        transport::Part::File {
+            data,
+            filename,
+            mime,
         }
     }
+
+    /// File name of the multipart image part
+    fn part_filename(&self, filename: Option<&str>) -> String {
+        filename
+            .or(self.name.as_deref())
+            .unwrap_or("image")
+            .to_string()
+    }
 }
@@ -0,0 +1,533 @@
+use std::fmt;
+
+use crate::{Error, ImageFormat};
+
+/// Kind of metadata block removed from an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataKind {
+    /// EXIF data, which may hold GPS coordinates and camera serial numbers
+    Exif,
+    /// XMP packet
+    Xmp,
+    /// IPTC data in a Photoshop resource block
+    Iptc,
+    /// ICC color profile
+    Icc,
+    /// Text comment
+    Comment,
+}
+
+impl fmt::Display for MetadataKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MetadataKind::Exif => "EXIF",
+            MetadataKind::Xmp => "XMP",
+            MetadataKind::Iptc => "IPTC",
+            MetadataKind::Icc => "ICC profile",
+            MetadataKind::Comment => "comment",
+        })
+    }
+}
+
+/// A metadata block removed from an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemovedBlock {
+    /// Kind of metadata
+    pub kind: MetadataKind,
+    /// Size of the block in bytes, including its header, less the size of
+    /// any minimal block kept in its place, or zero if that block is larger
+    pub size: usize,
+}
+
+/// Metadata removed from an image before it was uploaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrippedMetadata {
+    /// Format of the image
+    pub format: ImageFormat,
+    /// Removed blocks, in the order they appeared in the file
+    pub removed: Vec<RemovedBlock>,
+    /// EXIF orientation kept in place of the removed EXIF data, or `None`
+    /// if the image had no orientation other than the default
+    pub orientation: Option<u16>,
+}
+
+impl StrippedMetadata {
+    /// Whether a block of the given kind was removed
+    pub fn contains(&self, kind: MetadataKind) -> bool {
+        self.removed.iter().any(|block| block.kind == kind)
+    }
+
+    /// Total number of bytes removed
+    pub fn bytes_removed(&self) -> usize {
+        self.removed.iter().map(|block| block.size).sum()
+    }
+
+    fn remove(&mut self, kind: MetadataKind, size: usize) {
+        self.removed.push(RemovedBlock { kind, size });
+    }
+
+    /// Minimal EXIF data to keep in place of an EXIF block, holding only
+    /// the orientation of the image, if it has one
+    fn kept_exif(&mut self, tiff: &[u8]) -> Option<Vec<u8>> {
+        if self.orientation.is_some() {
+            return None;
+        }
+
+        let orientation = exif_orientation(tiff)?;
+        self.orientation = Some(orientation);
+        Some(orientation_exif(orientation))
+    }
+}
+
+/// What [strip_metadata_with] removes besides EXIF, XMP, IPTC and comments
+///
+/// # Examples
+///
+/// ```rust
+/// use imgbb::metadata::{strip_metadata_with, StripOptions};
+///
+/// let png = b"\x89PNG\r\n\x1A\n\0\0\0\0IEND\xAE\x42\x60\x82";
+/// let (stripped, _) = strip_metadata_with(png, StripOptions::new().remove_icc(true)).unwrap();
+/// assert_eq!(stripped, png);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StripOptions {
+    remove_icc: bool,
+}
+
+impl StripOptions {
+    /// Options that keep ICC profiles
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also remove ICC color profiles
+    ///
+    /// Profiles rarely identify anyone, but removing them changes how
+    /// images in a wide gamut color space, such as Display P3 photos from
+    /// phones, are displayed.
+    pub fn remove_icc(mut self, remove_icc: bool) -> Self {
+        self.remove_icc = remove_icc;
+        self
+    }
+
+    /// Whether blocks of the given kind are removed
+    fn removes(&self, kind: MetadataKind) -> bool {
+        kind != MetadataKind::Icc || self.remove_icc
+    }
+}
+
+/// Remove EXIF, XMP, IPTC and comment blocks from an image
+///
+/// Same as [strip_metadata_with] with the default [StripOptions], which
+/// keep ICC profiles.
+///
+/// # Examples
+///
+/// ```rust
+/// use imgbb::metadata::{strip_metadata, MetadataKind};
+///
+/// let jpeg = [
+///     0xFF, 0xD8, // SOI
+///     0xFF, 0xFE, 0x00, 0x07, b'h', b'e', b'l', b'l', b'o', // COM
+///     0xFF, 0xD9, // EOI
+/// ];
+///
+/// let (stripped, report) = strip_metadata(&jpeg).unwrap();
+/// assert_eq!(stripped, [0xFF, 0xD8, 0xFF, 0xD9]);
+/// assert!(report.contains(MetadataKind::Comment));
+/// ```
+///
+/// # Errors
+///
+/// See [strip_metadata_with]
+pub fn strip_metadata(data: &[u8]) -> Result<(Vec<u8>, StrippedMetadata), Error> {
+    strip_metadata_with(data, StripOptions::default())
+}
+
+/// Remove EXIF, XMP, IPTC, comment and, if requested, ICC blocks from an
+/// image
+///
+/// JPEG, PNG, WebP and GIF files are rewritten without the metadata
+/// blocks, and their pixel data is copied as is. BMP and ICO images are
+/// returned unchanged.
+///
+/// Viewers rotate photos according to their EXIF orientation, so when the
+/// EXIF data of a JPEG, PNG or WebP image holds a rotation or flip, a
+/// minimal EXIF block with only the orientation is kept in its place, and
+/// reported in [StrippedMetadata::orientation].
+///
+/// # Errors
+///
+/// Returns [InvalidParameters](Error::InvalidParameters) if the image is
+/// malformed, or in a format whose metadata cannot be removed, such as
+/// TIFF or HEIC
+pub fn strip_metadata_with(
+    data: &[u8],
+    options: StripOptions,
+) -> Result<(Vec<u8>, StrippedMetadata), Error> {
+    let format = ImageFormat::detect(data).ok_or_else(|| {
+        Error::invalid_parameters("Cannot strip metadata from an image of unknown format")
+    })?;
+
+    let mut metadata = StrippedMetadata {
+        format,
+        removed: Vec::new(),
+        orientation: None,
+    };
+    let stripped = match format {
+        ImageFormat::Jpeg => strip_jpeg(data, options, &mut metadata),
+        ImageFormat::Png => strip_png(data, options, &mut metadata),
+        ImageFormat::Webp => strip_webp(data, options, &mut metadata),
+        ImageFormat::Gif => strip_gif(data, options, &mut metadata),
+        ImageFormat::Bmp | ImageFormat::Ico => Some(data.to_vec()),
+        format => {
+            return Err(Error::invalid_parameters(format!(
+                "Cannot strip metadata from {} images",
+                format
+            )))
+        }
+    }
+    .ok_or_else(|| Error::invalid_parameters(format!("Malformed {} image", format)))?;
+
+    Ok((stripped, metadata))
+}
+
+/// Orientation tag of TIFF formatted EXIF data, if it is not the default
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| {
+        let bytes = tiff.get(pos..pos.checked_add(2)?)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |pos: usize| {
+        let bytes = tiff.get(pos..pos.checked_add(4)?)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    if u16_at(2)? != 42 {
+        return None;
+    }
+
+    let ifd = u32_at(4)? as usize;
+    for index in 0..u16_at(ifd)? as usize {
+        let entry = ifd.checked_add(2 + index * 12)?;
+        // Orientation, stored as a single SHORT
+        if u16_at(entry)? == 0x0112 && u16_at(entry + 2)? == 3 {
+            return u16_at(entry + 8).filter(|orientation| (2..=8).contains(orientation));
+        }
+    }
+    None
+}
+
+/// TIFF formatted EXIF data holding only an orientation tag
+fn orientation_exif(orientation: u16) -> Vec<u8> {
+    let mut tiff = b"MM\0\x2A\0\0\0\x08".to_vec();
+    tiff.extend_from_slice(&1u16.to_be_bytes());
+    tiff.extend_from_slice(&0x0112u16.to_be_bytes());
+    tiff.extend_from_slice(&3u16.to_be_bytes());
+    tiff.extend_from_slice(&1u32.to_be_bytes());
+    tiff.extend_from_slice(&orientation.to_be_bytes());
+    tiff.extend_from_slice(&[0; 2]);
+    // No next IFD
+    tiff.extend_from_slice(&[0; 4]);
+    tiff
+}
+
+/// Copy JPEG segments up to the image data, skipping metadata segments
+fn strip_jpeg(data: &[u8], options: StripOptions, metadata: &mut StrippedMetadata) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..2]);
+    let mut pos = 2;
+
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+
+        // Markers may be preceded by any number of fill bytes
+        let mut marker_pos = pos + 1;
+        while *data.get(marker_pos)? == 0xFF {
+            marker_pos += 1;
+        }
+
+        match data[marker_pos] {
+            // Start of scan and end of image: the rest is image data
+            0xDA | 0xD9 => {
+                out.extend_from_slice(&data[pos..]);
+                return Some(out);
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&data[pos..=marker_pos]);
+                pos = marker_pos + 1;
+            }
+            marker => {
+                let length = u16::from_be_bytes(data.get(marker_pos + 1..marker_pos + 3)?.try_into().ok()?);
+                let end = marker_pos + 1 + length as usize;
+                let payload = data.get(marker_pos + 3..end)?;
+
+                match jpeg_metadata(marker, payload).filter(|&kind| options.removes(kind)) {
+                    Some(MetadataKind::Exif) => {
+                        let kept = metadata
+                            .kept_exif(payload.get(6..).unwrap_or_default())
+                            .map(|tiff| {
+                                let payload = [&b"Exif\0\0"[..], &tiff].concat();
+                                let mut segment = vec![0xFF, 0xE1];
+                                segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+                                segment.extend_from_slice(&payload);
+                                segment
+                            })
+                            .unwrap_or_default();
+                        out.extend_from_slice(&kept);
+                        metadata.remove(MetadataKind::Exif, (end - pos).saturating_sub(kept.len()));
+                    }
+                    Some(kind) => metadata.remove(kind, end - pos),
+                    None => out.extend_from_slice(&data[pos..end]),
+                }
+                pos = end;
+            }
+        }
+    }
+}
+
+fn jpeg_metadata(marker: u8, payload: &[u8]) -> Option<MetadataKind> {
+    match marker {
+        0xE1 if payload.starts_with(b"Exif\0") => Some(MetadataKind::Exif),
+        0xE1 if payload.starts_with(b"http://ns.adobe.com/xap/1.0/\0")
+            || payload.starts_with(b"http://ns.adobe.com/xmp/extension/\0") =>
+        {
+            Some(MetadataKind::Xmp)
+        }
+        0xE2 if payload.starts_with(b"ICC_PROFILE\0") => Some(MetadataKind::Icc),
+        0xED if payload.starts_with(b"Photoshop 3.0\0") => Some(MetadataKind::Iptc),
+        0xFE => Some(MetadataKind::Comment),
+        _ => None,
+    }
+}
+
+/// CRC-32 of a PNG chunk type and payload
+fn png_crc(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Copy PNG chunks, skipping metadata chunks
+fn strip_png(data: &[u8], options: StripOptions, metadata: &mut StrippedMetadata) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..8]);
+    let mut pos = 8;
+
+    while pos < data.len() {
+        let length = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let kind = data.get(pos + 4..pos + 8)?;
+        let end = pos.checked_add(12)?.checked_add(length)?;
+        let payload = data.get(pos + 8..end - 4)?;
+
+        match png_metadata(kind, payload).filter(|&kind| options.removes(kind)) {
+            Some(MetadataKind::Exif) if kind == b"eXIf" => {
+                let kept = metadata
+                    .kept_exif(payload)
+                    .map(|tiff| {
+                        let body = [&b"eXIf"[..], &tiff].concat();
+                        let mut chunk = (tiff.len() as u32).to_be_bytes().to_vec();
+                        chunk.extend_from_slice(&body);
+                        chunk.extend_from_slice(&png_crc(&body).to_be_bytes());
+                        chunk
+                    })
+                    .unwrap_or_default();
+                out.extend_from_slice(&kept);
+                metadata.remove(MetadataKind::Exif, (end - pos).saturating_sub(kept.len()));
+            }
+            Some(kind) => metadata.remove(kind, end - pos),
+            None => out.extend_from_slice(&data[pos..end]),
+        }
+
+        pos = end;
+        if kind == b"IEND" {
+            break;
+        }
+    }
+
+    Some(out)
+}
+
+fn png_metadata(kind: &[u8], payload: &[u8]) -> Option<MetadataKind> {
+    match kind {
+        b"eXIf" => Some(MetadataKind::Exif),
+        b"iCCP" => Some(MetadataKind::Icc),
+        b"tEXt" | b"zTXt" | b"iTXt" => {
+            let keyword = payload.split(|&byte| byte == 0).next().unwrap_or_default();
+            let keyword = String::from_utf8_lossy(keyword).to_ascii_lowercase();
+
+            // ImageMagick stores other metadata as "Raw profile type <name>"
+            Some(match keyword.strip_prefix("raw profile type ") {
+                Some("exif" | "app1") => MetadataKind::Exif,
+                Some("xmp") => MetadataKind::Xmp,
+                Some("iptc" | "8bim") => MetadataKind::Iptc,
+                Some("icc" | "icm") => MetadataKind::Icc,
+                _ if keyword == "xml:com.adobe.xmp" => MetadataKind::Xmp,
+                _ => MetadataKind::Comment,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Copy WebP chunks, skipping metadata chunks and clearing the flags of
+/// the removed ones
+fn strip_webp(data: &[u8], options: StripOptions, metadata: &mut StrippedMetadata) -> Option<Vec<u8>> {
+    /// VP8X flag announcing an ICC chunk
+    const ICC_FLAG: u8 = 0x20;
+    /// VP8X flag announcing an EXIF chunk
+    const EXIF_FLAG: u8 = 0x08;
+    /// VP8X flag announcing an XMP chunk
+    const XMP_FLAG: u8 = 0x04;
+
+    let riff_end = (u32::from_le_bytes(data.get(4..8)?.try_into().ok()?) as usize)
+        .checked_add(8)?
+        .min(data.len());
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..12]);
+    let mut pos = 12;
+    let mut flags_pos = None;
+    let mut kept_flags = 0;
+
+    while pos < riff_end {
+        let kind = data.get(pos..pos + 4)?;
+        let length = u32::from_le_bytes(data.get(pos + 4..pos + 8)?.try_into().ok()?) as usize;
+        let end = (pos + 8).checked_add(length)?.checked_add(length % 2)?.min(riff_end);
+        let payload = data.get(pos + 8..pos + 8 + length)?;
+
+        let block = match kind {
+            b"EXIF" => Some(MetadataKind::Exif),
+            b"XMP " => Some(MetadataKind::Xmp),
+            b"ICCP" => Some(MetadataKind::Icc),
+            _ => None,
+        };
+
+        match block.filter(|&kind| options.removes(kind)) {
+            Some(MetadataKind::Exif) => {
+                // Some encoders keep the JPEG "Exif" prefix
+                let tiff = payload.strip_prefix(b"Exif\0\0").unwrap_or(payload);
+                let kept = metadata
+                    .kept_exif(tiff)
+                    .map(|tiff| {
+                        kept_flags |= EXIF_FLAG;
+                        let mut chunk = b"EXIF".to_vec();
+                        chunk.extend_from_slice(&(tiff.len() as u32).to_le_bytes());
+                        chunk.extend_from_slice(&tiff);
+                        chunk
+                    })
+                    .unwrap_or_default();
+                out.extend_from_slice(&kept);
+                metadata.remove(MetadataKind::Exif, (end - pos).saturating_sub(kept.len()));
+            }
+            Some(kind) => metadata.remove(kind, end - pos),
+            None => {
+                if block == Some(MetadataKind::Icc) {
+                    kept_flags |= ICC_FLAG;
+                }
+                if kind == b"VP8X" {
+                    flags_pos = Some(out.len() + 8);
+                }
+                out.extend_from_slice(&data[pos..end]);
+            }
+        }
+        pos = end;
+    }
+
+    if let Some(flags_pos) = flags_pos {
+        let flags = out.get_mut(flags_pos)?;
+        *flags = (*flags & !(ICC_FLAG | EXIF_FLAG | XMP_FLAG)) | kept_flags;
+    }
+
+    let riff_size = u32::try_from(out.len() - 8).ok()?;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
+
+/// Copy GIF blocks, skipping comment extensions and application extensions
+/// holding XMP data or an ICC profile
+fn strip_gif(data: &[u8], options: StripOptions, metadata: &mut StrippedMetadata) -> Option<Vec<u8>> {
+    let mut pos = 13 + color_table_len(*data.get(10)?);
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(data.get(..pos)?);
+
+    loop {
+        match *data.get(pos)? {
+            // Trailer
+            0x3B => {
+                out.extend_from_slice(&data[pos..]);
+                return Some(out);
+            }
+            // Extension
+            0x21 => {
+                let end = skip_sub_blocks(data, pos + 2)?;
+                let block = match *data.get(pos + 1)? {
+                    0xFE => Some(MetadataKind::Comment),
+                    // Application extensions start with an 11 byte identifier
+                    0xFF => match data.get(pos + 2..pos + 14)? {
+                        b"\x0BXMP DataXMP" => Some(MetadataKind::Xmp),
+                        b"\x0BICCRGBG1012" => Some(MetadataKind::Icc),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+
+                match block.filter(|&kind| options.removes(kind)) {
+                    Some(kind) => metadata.remove(kind, end - pos),
+                    None => out.extend_from_slice(data.get(pos..end)?),
+                }
+                pos = end;
+            }
+            // Image descriptor, followed by the LZW minimum code size
+            0x2C => {
+                let start = pos + 10 + color_table_len(*data.get(pos + 9)?);
+                let end = skip_sub_blocks(data, start + 1)?;
+                out.extend_from_slice(data.get(pos..end)?);
+                pos = end;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Length of the color table announced by GIF packed flags
+fn color_table_len(flags: u8) -> usize {
+    if flags & 0x80 == 0 {
+        0
+    } else {
+        3 << ((flags & 0x07) + 1)
+    }
+}
+
+/// Position after the GIF data sub-blocks starting at `pos`
+fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let size = *data.get(pos)? as usize;
+        pos += 1 + size;
+        if size == 0 {
+            return Some(pos);
+        }
+    }
+}
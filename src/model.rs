@@ -1,36 +1,77 @@
-use serde::Deserialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::embed::{Embed, EmbedFormat, EmbedUrls};
+use crate::error::ApiErrorCode;
+use crate::Error;
 
 /// Response from the ImgBB API
 /// 
 /// The API returns a JSON structure that includes the upload data,
 /// as well as status and success information.
-#[derive(Debug, Deserialize, Clone)]
+///
+/// Numeric fields accept both JSON numbers and numeric strings, as ImgBB
+/// returns either depending on the endpoint. Empty strings are read as
+/// missing values.
+///
+/// # JSON representation
+///
+/// The models serialize to the field names used by the API. Numbers are
+/// always written as JSON numbers, `success` as a boolean, error codes as
+/// their numeric value and missing values as `null`, so serialized values
+/// deserialize back to the same data. With the `schemars` feature, the
+/// models implement `JsonSchema` describing this form.
+///
+/// ```rust
+/// use imgbb::model::Response;
+///
+/// let response: Response = serde_json::from_str(
+///     r#"{"data": {"id": "2ndCYJK", "width": "640"}, "success": true, "status": 200}"#,
+/// ).unwrap();
+///
+/// let json = serde_json::to_value(&response).unwrap();
+/// assert_eq!(json["data"]["width"], 640);
+/// assert_eq!(json["data"]["title"], serde_json::Value::Null);
+///
+/// let stored: Response = serde_json::from_value(json.clone()).unwrap();
+/// assert_eq!(serde_json::to_value(&stored).unwrap(), json);
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Response {
     /// The image data information if the upload was successful
     pub data: Option<Data>,
     /// Boolean indicating if the request was successful
+    #[serde(default, deserialize_with = "lenient::flag")]
     pub success: Option<bool>,
     /// HTTP status code
+    #[serde(default, deserialize_with = "lenient::number")]
     pub status: Option<u16>,
     /// Error message if the request failed
     pub error: Option<ErrorResponse>,
 }
 
 /// Error information returned by the ImgBB API when a request fails
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ErrorResponse {
     /// Error message
     pub message: Option<String>,
     /// Error code
-    pub code: Option<u16>,
+    #[serde(default, deserialize_with = "lenient::error_code")]
+    pub code: Option<ApiErrorCode>,
 }
 
 /// Detailed information about an uploaded image
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Data {
     /// Unique ID of the uploaded image
+    #[serde(default, deserialize_with = "lenient::string")]
     pub id: Option<String>,
     /// Title of the image (if provided during upload)
+    #[serde(default, deserialize_with = "lenient::string")]
     pub title: Option<String>,
     /// URL to view the image on ImgBB website
     pub url_viewer: Option<String>,
@@ -39,14 +80,19 @@ pub struct Data {
     /// Display URL (typically used in HTML)
     pub display_url: Option<String>,
     /// Width of the image in pixels
-    pub width: Option<u16>,
+    #[serde(default, deserialize_with = "lenient::number")]
+    pub width: Option<u32>,
     /// Height of the image in pixels
-    pub height: Option<u16>,
+    #[serde(default, deserialize_with = "lenient::number")]
+    pub height: Option<u32>,
     /// Size of the image in bytes
-    pub size: Option<u32>,
+    #[serde(default, deserialize_with = "lenient::number")]
+    pub size: Option<u64>,
     /// Unix timestamp of when the image was uploaded
+    #[serde(default, deserialize_with = "lenient::number")]
     pub time: Option<u64>,
     /// Expiration time in seconds, if set
+    #[serde(default, deserialize_with = "lenient::number")]
     pub expiration: Option<u64>,
     /// Full-size image information
     pub image: Option<Image>,
@@ -59,16 +105,331 @@ pub struct Data {
 }
 
 /// Information about a specific image variant (original, thumbnail, etc.)
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Image {
     /// Original filename
+    #[serde(default, deserialize_with = "lenient::string")]
     pub filename: Option<String>,
     /// Name of the image
+    #[serde(default, deserialize_with = "lenient::string")]
     pub name: Option<String>,
     /// MIME type (e.g., "image/jpeg")
     pub mime: Option<String>,
     /// File extension (e.g., "jpg")
+    #[serde(default, deserialize_with = "lenient::string")]
     pub extension: Option<String>,
     /// Direct URL to this image variant
     pub url: Option<String>,
 }
+
+/// A successfully uploaded image
+///
+/// Unlike [Data], the fields needed to use and delete the image are checked
+/// when the response is parsed, so they are always present.
+///
+/// It serializes like [Data], so stored images can be read back either as
+/// an `UploadedImage` or as [Data].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::ImgBB;
+///
+/// async fn example() -> Result<(), imgbb::Error> {
+///     let imgbb = ImgBB::new("your_api_key");
+///
+///     let image = imgbb.upload_file("path/to/image.jpg").await?;
+///     println!("{} ({}x{}, {} bytes)", image.url, image.width, image.height, image.size);
+///     println!("Delete it at {}", image.delete_url);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UploadedImage {
+    /// Unique ID of the uploaded image
+    pub id: String,
+    /// Title of the image (if provided during upload)
+    pub title: Option<String>,
+    /// URL to view the image on ImgBB website
+    pub url_viewer: Option<String>,
+    /// Direct URL to the image
+    pub url: String,
+    /// Display URL (typically used in HTML)
+    pub display_url: String,
+    /// Width of the image in pixels
+    pub width: u32,
+    /// Height of the image in pixels
+    pub height: u32,
+    /// Size of the image in bytes
+    pub size: u64,
+    /// Unix timestamp of when the image was uploaded
+    pub time: Option<u64>,
+    /// Expiration time in seconds, if set
+    pub expiration: Option<u64>,
+    /// Full-size image information
+    pub image: Option<Image>,
+    /// Thumbnail image information
+    pub thumb: Option<Image>,
+    /// Medium-size image information
+    pub medium: Option<Image>,
+    /// URL to delete the image
+    pub delete_url: String,
+}
+
+/// Generate the upload time and expiration accessors shared by [Data] and
+/// [UploadedImage]
+macro_rules! lifetime_accessors {
+    ($type:ty) => {
+        impl $type {
+            /// Time the image was uploaded, if reported by the API
+            pub fn uploaded_at(&self) -> Option<SystemTime> {
+                self.time
+                    .and_then(|time| UNIX_EPOCH.checked_add(Duration::from_secs(time)))
+            }
+
+            /// Lifetime the image was uploaded with
+            ///
+            /// Returns `None` if the image does not expire.
+            pub fn lifetime(&self) -> Option<Duration> {
+                self.expiration
+                    .filter(|&expiration| expiration > 0)
+                    .map(Duration::from_secs)
+            }
+
+            /// Time at which ImgBB deletes the image
+            ///
+            /// Returns `None` if the image does not expire or the upload
+            /// time is unknown.
+            pub fn expires_at(&self) -> Option<SystemTime> {
+                self.uploaded_at()?.checked_add(self.lifetime()?)
+            }
+
+            /// Whether the image has expired at `now`
+            ///
+            /// Images without an expiration time never expire.
+            pub fn is_expired(&self, now: SystemTime) -> bool {
+                self.expires_at().is_some_and(|expires_at| expires_at <= now)
+            }
+
+            /// Time left before the image expires, counted from `now`
+            ///
+            /// Returns `None` if the image does not expire or the upload
+            /// time is unknown, and [Duration::ZERO] once it has expired.
+            pub fn remaining_lifetime(&self, now: SystemTime) -> Option<Duration> {
+                let expires_at = self.expires_at()?;
+                Some(expires_at.duration_since(now).unwrap_or(Duration::ZERO))
+            }
+
+            /// [Upload time](Self::uploaded_at) as a `chrono` date time
+            #[cfg(feature = "chrono")]
+            pub fn uploaded_at_chrono(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+                self.uploaded_at().map(Into::into)
+            }
+
+            /// [Expiration time](Self::expires_at) as a `chrono` date time
+            #[cfg(feature = "chrono")]
+            pub fn expires_at_chrono(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+                self.expires_at().map(Into::into)
+            }
+
+            /// [Upload time](Self::uploaded_at) as a `time` date time
+            #[cfg(feature = "time")]
+            pub fn uploaded_at_time(&self) -> Option<time::OffsetDateTime> {
+                self.uploaded_at().map(Into::into)
+            }
+
+            /// [Expiration time](Self::expires_at) as a `time` date time
+            #[cfg(feature = "time")]
+            pub fn expires_at_time(&self) -> Option<time::OffsetDateTime> {
+                self.expires_at().map(Into::into)
+            }
+        }
+    };
+}
+
+lifetime_accessors!(Data);
+lifetime_accessors!(UploadedImage);
+
+/// URL of an image variant, if ImgBB created it
+fn variant_url(variant: &Option<Image>) -> Option<&str> {
+    variant.as_ref().and_then(|image| image.url.as_deref())
+}
+
+/// Default alt text of an embed snippet
+fn default_alt<'a>(title: &'a Option<String>, image: &'a Option<Image>, id: Option<&'a str>) -> &'a str {
+    title
+        .as_deref()
+        .or_else(|| image.as_ref().and_then(|image| image.name.as_deref()))
+        .or(id)
+        .unwrap_or_default()
+}
+
+impl Data {
+    /// Embed snippet for the image in the given format
+    ///
+    /// Returns `None` if the response has no image URL. See [Embed].
+    pub fn embed(&self, format: EmbedFormat) -> Option<Embed<'_>> {
+        let urls = EmbedUrls {
+            full: self.url.as_deref()?,
+            medium: variant_url(&self.medium),
+            thumb: variant_url(&self.thumb),
+            viewer: self.url_viewer.as_deref(),
+        };
+
+        Some(Embed::new(format, urls, default_alt(&self.title, &self.image, self.id.as_deref())))
+    }
+}
+
+impl UploadedImage {
+    /// Embed snippet for the image in the given format
+    ///
+    /// See [Embed].
+    pub fn embed(&self, format: EmbedFormat) -> Embed<'_> {
+        let urls = EmbedUrls {
+            full: &self.url,
+            medium: variant_url(&self.medium),
+            thumb: variant_url(&self.thumb),
+            viewer: self.url_viewer.as_deref(),
+        };
+
+        Embed::new(format, urls, default_alt(&self.title, &self.image, Some(&self.id)))
+    }
+}
+
+/// Take a required field out of the response data
+fn required<T>(value: Option<T>, field: &str) -> Result<T, Error> {
+    value.ok_or_else(|| Error::MissingField(format!("data.{}", field)))
+}
+
+impl TryFrom<Data> for UploadedImage {
+    type Error = Error;
+
+    fn try_from(data: Data) -> Result<Self, Error> {
+        Ok(Self {
+            id: required(data.id, "id")?,
+            title: data.title,
+            url_viewer: data.url_viewer,
+            url: required(data.url, "url")?,
+            display_url: required(data.display_url, "display_url")?,
+            width: required(data.width, "width")?,
+            height: required(data.height, "height")?,
+            size: required(data.size, "size")?,
+            time: data.time,
+            expiration: data.expiration,
+            image: data.image,
+            thumb: data.thumb,
+            medium: data.medium,
+            delete_url: required(data.delete_url, "delete_url")?,
+        })
+    }
+}
+
+impl TryFrom<Response> for UploadedImage {
+    type Error = Error;
+
+    fn try_from(response: Response) -> Result<Self, Error> {
+        response
+            .data
+            .ok_or_else(|| Error::MissingField("data".to_string()))?
+            .try_into()
+    }
+}
+
+/// Deserializers for fields that ImgBB sends with inconsistent JSON types
+mod lenient {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::de::{Deserializer, Error, IgnoredAny};
+    use serde::Deserialize;
+
+    use crate::error::ApiErrorCode;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString<T> {
+        Number(T),
+        String(String),
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Number(u8),
+        String(String),
+        Object(IgnoredAny),
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(serde_json::Number),
+    }
+
+    /// A number given as a JSON number or a numeric string
+    pub(super) fn number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + FromStr,
+        T::Err: Display,
+    {
+        match Option::<NumberOrString<T>>::deserialize(deserializer)? {
+            Some(NumberOrString::Number(number)) => Ok(Some(number)),
+            Some(NumberOrString::String(value)) if value.trim().is_empty() => Ok(None),
+            Some(NumberOrString::String(value)) => value
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|e| D::Error::custom(format!("invalid number '{}': {}", value, e))),
+            None => Ok(None),
+        }
+    }
+
+    /// A string that may be sent as a JSON number, such as a numeric file name
+    pub(super) fn string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(
+            Option::<StringOrNumber>::deserialize(deserializer)?.map(|value| match value {
+                StringOrNumber::String(value) => value,
+                StringOrNumber::Number(number) => number.to_string(),
+            }),
+        )
+    }
+
+    /// A boolean given as a JSON boolean, 0 or 1, or a string
+    ///
+    /// Some endpoints report success as an object holding a message, which
+    /// is read as `true`.
+    pub(super) fn flag<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Flag>::deserialize(deserializer)? {
+            Some(Flag::Bool(value)) => Ok(Some(value)),
+            Some(Flag::Number(value)) => Ok(Some(value != 0)),
+            Some(Flag::String(value)) => match value.trim() {
+                "" => Ok(None),
+                "true" | "1" => Ok(Some(true)),
+                "false" | "0" => Ok(Some(false)),
+                _ => Err(D::Error::custom(format!("invalid boolean '{}'", value))),
+            },
+            Some(Flag::Object(_)) => Ok(Some(true)),
+            None => Ok(None),
+        }
+    }
+
+    /// An error code given as a JSON number or a numeric string
+    pub(super) fn error_code<'de, D>(deserializer: D) -> Result<Option<ApiErrorCode>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(number::<D, u16>(deserializer)?.map(ApiErrorCode::from))
+    }
+}
@@ -0,0 +1,248 @@
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageReader};
+
+use crate::{Error, ImageFormat};
+
+/// JPEG qualities tried in turn until the image fits
+const JPEG_QUALITIES: [u8; 6] = [90, 80, 70, 60, 50, 40];
+/// Factor applied to the dimensions when no encoding fits
+const SCALE_STEP: f32 = 0.75;
+/// Longest side below which the image is not shrunk any further
+const MIN_DIMENSION: u32 = 64;
+
+/// Limits an image is shrunk to fit before it is uploaded
+///
+/// Set with [UploaderBuilder::fit_within](crate::UploaderBuilder::fit_within).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FitPolicy {
+    /// Largest encoded size in bytes
+    pub max_bytes: u64,
+    /// Largest width or height in pixels
+    pub max_dimension: u32,
+}
+
+/// How an image was changed to fit a [FitPolicy]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transformation {
+    /// Format of the original image, if recognized
+    pub original_format: Option<ImageFormat>,
+    /// Size of the original image in bytes
+    pub original_size: u64,
+    /// Width and height of the original image
+    pub original_dimensions: (u32, u32),
+    /// Format of the uploaded image
+    pub format: ImageFormat,
+    /// Size of the uploaded image in bytes
+    pub size: u64,
+    /// Width and height of the uploaded image
+    pub dimensions: (u32, u32),
+    /// JPEG quality used, if the image was encoded as JPEG
+    pub quality: Option<u8>,
+    /// Whether the original image had an ICC profile that could not be
+    /// embedded in the uploaded image
+    pub icc_profile_dropped: bool,
+}
+
+impl Transformation {
+    /// Whether the image was resized
+    pub fn resized(&self) -> bool {
+        self.dimensions != self.original_dimensions
+    }
+
+    /// Whether the image was encoded in another format
+    pub fn converted(&self) -> bool {
+        self.original_format != Some(self.format)
+    }
+}
+
+/// Shrink an image until it fits the policy
+///
+/// The image is first scaled down to the maximum dimension, then encoded
+/// in its own format, as lossless WebP, and as JPEG at decreasing
+/// qualities until one fits. Images with transparency are never encoded
+/// as JPEG. If nothing fits, the image is scaled down further and the
+/// encodings are tried again.
+///
+/// Returns `None` if the image already fits. The EXIF orientation is
+/// applied to the pixels, as the re-encoded image has no EXIF data, and the
+/// ICC profile is kept. Animated images keep only their first frame.
+///
+/// # Errors
+///
+/// Returns [InvalidParameters](Error::InvalidParameters) if a limit of the
+/// policy is zero, [ImageProcessing](Error::ImageProcessing) if the image
+/// cannot be decoded, or [ImageTooLarge](Error::ImageTooLarge) if it cannot
+/// be made to fit.
+pub(crate) fn fit(
+    data: &[u8],
+    policy: &FitPolicy,
+) -> Result<Option<(Vec<u8>, Transformation)>, Error> {
+    if policy.max_bytes == 0 || policy.max_dimension == 0 {
+        return Err(Error::invalid_parameters(
+            "The size and dimension limits of fit_within must be greater than zero",
+        ));
+    }
+
+    let original_size = data.len() as u64;
+    let (width, height) = reader(data)?.into_dimensions()?;
+
+    if original_size <= policy.max_bytes && width.max(height) <= policy.max_dimension {
+        return Ok(None);
+    }
+
+    let original_format = ImageFormat::detect(data);
+    let mut decoder = reader(data)?.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let icc_profile = decoder.icc_profile()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    let original_dimensions = (image.width(), image.height());
+
+    if width.max(height) > policy.max_dimension {
+        image = image.resize(policy.max_dimension, policy.max_dimension, FilterType::Lanczos3);
+    }
+
+    loop {
+        let encoded = encode_within(&image, original_format, icc_profile.as_deref(), policy.max_bytes)?;
+        if let Some(encoded) = encoded {
+            let transformation = Transformation {
+                original_format,
+                original_size,
+                original_dimensions,
+                format: encoded.format,
+                size: encoded.data.len() as u64,
+                dimensions: (image.width(), image.height()),
+                quality: encoded.quality,
+                icc_profile_dropped: icc_profile.is_some() && !encoded.icc_profile_kept,
+            };
+            return Ok(Some((encoded.data, transformation)));
+        }
+
+        let longest = image.width().max(image.height());
+        if longest <= MIN_DIMENSION {
+            return Err(Error::image_too_large(original_size, policy.max_bytes));
+        }
+
+        let target = ((longest as f32 * SCALE_STEP) as u32).max(MIN_DIMENSION);
+        image = image.resize(target, target, FilterType::Lanczos3);
+    }
+}
+
+/// Message of a panic caught while processing an image
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "the image codec panicked".to_string()),
+    }
+}
+
+fn reader(data: &[u8]) -> Result<ImageReader<Cursor<&[u8]>>, Error> {
+    Ok(ImageReader::new(Cursor::new(data)).with_guessed_format()?)
+}
+
+/// An encoded image
+struct Encoded {
+    data: Vec<u8>,
+    format: ImageFormat,
+    quality: Option<u8>,
+    icc_profile_kept: bool,
+}
+
+/// First encoding of the image that fits, trying the smallest changes first
+fn encode_within(
+    image: &DynamicImage,
+    original: Option<ImageFormat>,
+    icc_profile: Option<&[u8]>,
+    max_bytes: u64,
+) -> Result<Option<Encoded>, Error> {
+    let fits = |data: &Vec<u8>| data.len() as u64 <= max_bytes;
+
+    if original == Some(ImageFormat::Png) {
+        let (data, icc_profile_kept) = encode_png(image, icc_profile)?;
+        if fits(&data) {
+            return Ok(Some(Encoded {
+                data,
+                format: ImageFormat::Png,
+                quality: None,
+                icc_profile_kept,
+            }));
+        }
+    }
+
+    if original != Some(ImageFormat::Jpeg) {
+        let (data, icc_profile_kept) = encode_webp(image, icc_profile)?;
+        if fits(&data) {
+            return Ok(Some(Encoded {
+                data,
+                format: ImageFormat::Webp,
+                quality: None,
+                icc_profile_kept,
+            }));
+        }
+    }
+
+    if !image.color().has_alpha() {
+        for quality in JPEG_QUALITIES {
+            let (data, icc_profile_kept) = encode_jpeg(image, quality, icc_profile)?;
+            if fits(&data) {
+                return Ok(Some(Encoded {
+                    data,
+                    format: ImageFormat::Jpeg,
+                    quality: Some(quality),
+                    icc_profile_kept,
+                }));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Give the encoder the ICC profile of the original image, returning
+/// whether it was accepted
+fn embed_icc_profile<E>(encoder: &mut E, icc_profile: Option<&[u8]>) -> bool
+where
+    E: ImageEncoder,
+{
+    icc_profile.is_none_or(|profile| encoder.set_icc_profile(profile.to_vec()).is_ok())
+}
+
+fn encode_png(image: &DynamicImage, icc_profile: Option<&[u8]>) -> Result<(Vec<u8>, bool), Error> {
+    let mut encoded = Vec::new();
+    let mut encoder = PngEncoder::new_with_quality(
+        &mut encoded,
+        CompressionType::Best,
+        image::codecs::png::FilterType::Adaptive,
+    );
+    let kept = embed_icc_profile(&mut encoder, icc_profile);
+    image.write_with_encoder(encoder)?;
+    Ok((encoded, kept))
+}
+
+fn encode_webp(image: &DynamicImage, icc_profile: Option<&[u8]>) -> Result<(Vec<u8>, bool), Error> {
+    let mut encoded = Vec::new();
+    let mut encoder = WebPEncoder::new_lossless(&mut encoded);
+    let kept = embed_icc_profile(&mut encoder, icc_profile);
+    DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(encoder)?;
+    Ok((encoded, kept))
+}
+
+fn encode_jpeg(
+    image: &DynamicImage,
+    quality: u8,
+    icc_profile: Option<&[u8]>,
+) -> Result<(Vec<u8>, bool), Error> {
+    let mut encoded = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(&mut encoded, quality);
+    let kept = embed_icc_profile(&mut encoder, icc_profile);
+    DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(encoder)?;
+    Ok((encoded, kept))
+}
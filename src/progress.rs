@@ -0,0 +1,214 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use futures_util::stream::{self, StreamExt};
+
+use crate::transport::ByteStream;
+
+/// Snapshot of the progress of a single upload
+///
+/// Sizes refer to the image payload of the request: the url-encoded base64
+/// value for form uploads, or the raw bytes for multipart uploads. If an
+/// upload is retried, progress starts over for the new attempt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Raw image bytes read from the source and encoded so far
+    pub bytes_encoded: u64,
+    /// Image payload bytes written to the request body so far
+    pub bytes_sent: u64,
+    /// Total size of the image payload, if known before sending
+    pub total_bytes: Option<u64>,
+}
+
+impl Progress {
+    /// Fraction of the payload sent, between 0 and 1, if the total is known
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total_bytes {
+            Some(0) => Some(1.0),
+            Some(total) => Some(self.bytes_sent as f64 / total as f64),
+            None => None,
+        }
+    }
+}
+
+/// Aggregate progress of a batch upload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchProgress {
+    /// Number of items in the batch
+    pub items_total: usize,
+    /// Number of items that finished, successfully or not
+    pub items_completed: usize,
+    /// Number of items that failed
+    pub items_failed: usize,
+    /// Raw image bytes encoded so far, across all items
+    pub bytes_encoded: u64,
+    /// Image payload bytes sent so far, across all items
+    pub bytes_sent: u64,
+    /// Sum of the payload sizes known so far
+    pub total_bytes: u64,
+}
+
+/// Callback receiving progress updates
+type ProgressCallback = Arc<dyn Fn(Progress) + Send + Sync>;
+
+/// Progress callbacks registered on an uploader builder
+#[derive(Clone, Default)]
+pub(crate) struct ProgressObservers(Vec<ProgressCallback>);
+
+impl ProgressObservers {
+    pub(crate) fn push<F>(&mut self, callback: F)
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        self.0.push(Arc::new(callback));
+    }
+
+    /// Start tracking a new upload attempt, if anyone is listening
+    pub(crate) fn tracker(&self) -> Option<ProgressTracker> {
+        (!self.0.is_empty()).then(|| ProgressTracker {
+            callbacks: self.0.as_slice().into(),
+            state: Arc::new(Mutex::new(Progress::default())),
+        })
+    }
+}
+
+impl fmt::Debug for ProgressObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProgressObservers").field(&self.0.len()).finish()
+    }
+}
+
+/// Progress of a single upload attempt, shared by the streams of its body
+#[derive(Clone)]
+pub(crate) struct ProgressTracker {
+    callbacks: Arc<[ProgressCallback]>,
+    state: Arc<Mutex<Progress>>,
+}
+
+impl ProgressTracker {
+    /// Apply a change and notify every callback
+    fn update<F>(&self, change: F)
+    where
+        F: FnOnce(&mut Progress),
+    {
+        let snapshot = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            change(&mut state);
+            *state
+        };
+
+        for callback in self.callbacks.iter() {
+            callback(snapshot);
+        }
+    }
+
+    pub(crate) fn total(&self, total: Option<u64>) {
+        self.update(|progress| progress.total_bytes = total);
+    }
+
+    pub(crate) fn encoded(&self, bytes: usize) {
+        self.update(|progress| progress.bytes_encoded += bytes as u64);
+    }
+
+    pub(crate) fn sent(&self, bytes: usize) {
+        self.update(|progress| progress.bytes_sent += bytes as u64);
+    }
+
+    /// Count every chunk of an already encoded payload as sent
+    pub(crate) fn count_sent(&self, stream: ByteStream) -> ByteStream {
+        let tracker = self.clone();
+        Box::pin(stream.inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                tracker.sent(chunk.len());
+            }
+        }))
+    }
+
+    /// Count every chunk of a raw payload as both encoded and sent
+    pub(crate) fn count_raw(&self, stream: ByteStream) -> ByteStream {
+        let tracker = self.clone();
+        Box::pin(stream.inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                tracker.update(|progress| {
+                    progress.bytes_encoded += chunk.len() as u64;
+                    progress.bytes_sent += chunk.len() as u64;
+                });
+            }
+        }))
+    }
+}
+
+/// Split in-memory data into a stream of chunks without copying
+pub(crate) fn chunked(data: Bytes, chunk_size: usize) -> ByteStream {
+    let chunks = (0..data.len())
+        .step_by(chunk_size.max(1))
+        .map(move |start| Ok(data.slice(start..(start + chunk_size).min(data.len()))))
+        .collect::<Vec<_>>();
+
+    Box::pin(stream::iter(chunks))
+}
+
+/// Aggregates the progress of the items of a batch
+pub(crate) struct BatchTracker {
+    callback: Arc<dyn Fn(BatchProgress) + Send + Sync>,
+    state: Mutex<BatchState>,
+}
+
+struct BatchState {
+    items: Vec<Progress>,
+    completed: usize,
+    failed: usize,
+}
+
+impl BatchTracker {
+    pub(crate) fn new<F>(items: usize, callback: F) -> Self
+    where
+        F: Fn(BatchProgress) + Send + Sync + 'static,
+    {
+        Self {
+            callback: Arc::new(callback),
+            state: Mutex::new(BatchState {
+                items: vec![Progress::default(); items],
+                completed: 0,
+                failed: 0,
+            }),
+        }
+    }
+
+    fn update<F>(&self, change: F)
+    where
+        F: FnOnce(&mut BatchState),
+    {
+        let snapshot = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            change(&mut state);
+
+            BatchProgress {
+                items_total: state.items.len(),
+                items_completed: state.completed,
+                items_failed: state.failed,
+                bytes_encoded: state.items.iter().map(|p| p.bytes_encoded).sum(),
+                bytes_sent: state.items.iter().map(|p| p.bytes_sent).sum(),
+                total_bytes: state.items.iter().filter_map(|p| p.total_bytes).sum(),
+            }
+        };
+
+        (self.callback)(snapshot);
+    }
+
+    /// Record the progress of one item
+    pub(crate) fn item(&self, index: usize, progress: Progress) {
+        self.update(|state| state.items[index] = progress);
+    }
+
+    /// Record that an item finished
+    pub(crate) fn finished(&self, success: bool) {
+        self.update(|state| {
+            state.completed += 1;
+            if !success {
+                state.failed += 1;
+            }
+        });
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Client-side token bucket rate limiter
+///
+/// Every request made by a client configured with a limiter, including
+/// requests made by its uploaders and retried attempts, waits for a token
+/// before it is sent. The bucket holds up to `burst` tokens and refills at
+/// the configured rate.
+///
+/// Cloning a limiter yields a handle to the same bucket, so one limiter can
+/// be shared by several clients.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::{ImgBB, RateLimiter};
+///
+/// // At most 2 requests per second, with bursts of up to 5 requests
+/// let imgbb = ImgBB::builder("your_api_key")
+///     .rate_limiter(RateLimiter::per_second(2).burst(5))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Maximum number of stored tokens
+    capacity: f64,
+    /// Tokens added per second
+    rate: f64,
+    /// Available tokens, negative when requests are queued
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `requests` requests every `period`
+    ///
+    /// The burst size defaults to `requests`. A `requests` value of 0 is
+    /// treated as 1.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        let requests = f64::from(requests.max(1));
+        let period = period.as_secs_f64().max(f64::EPSILON);
+
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                capacity: requests,
+                rate: requests / period,
+                tokens: requests,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Creates a limiter allowing `requests` requests per second
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Creates a limiter allowing `requests` requests per minute
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Set the maximum number of requests that may be sent at once
+    ///
+    /// The bucket starts full. A `burst` value of 0 is treated as 1.
+    pub fn burst(self, burst: u32) -> Self {
+        {
+            let mut bucket = self.lock();
+            bucket.capacity = f64::from(burst.max(1));
+            bucket.tokens = bucket.capacity;
+        }
+        self
+    }
+
+    /// Take a token and return how long to wait before sending
+    fn reserve(&self) -> Duration {
+        let mut bucket = self.lock();
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(bucket.capacity);
+        bucket.last_refill = now;
+
+        bucket.tokens -= 1.0;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / bucket.rate)
+        }
+    }
+
+    /// Wait until a request may be sent
+    pub(crate) async fn acquire(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Block the current thread until a request may be sent
+    #[cfg(feature = "blocking")]
+    pub(crate) fn acquire_blocking(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.bucket.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
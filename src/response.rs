@@ -0,0 +1,153 @@
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+use crate::error::{ApiErrorCode, RequestKind, ResponseContext};
+use crate::model::{ErrorResponse, Response};
+use crate::Error;
+
+/// Convert an error object returned by the API into an [Error](Error)
+///
+/// Every call path goes through this table, so a given ImgBB error code
+/// always becomes the same variant. Error objects without a code are
+/// classified by their HTTP status instead.
+fn map_api_error(error: ErrorResponse, mut context: ResponseContext) -> Error {
+    let message = error.message.unwrap_or_else(|| "Unknown error".to_string());
+    context.code = error.code;
+    let context = Some(Box::new(context));
+
+    match error.code {
+        Some(ApiErrorCode::InvalidApiKey) => Error::InvalidApiKey { context },
+        Some(ApiErrorCode::InvalidBase64) => Error::InvalidBase64Data { context },
+        Some(ApiErrorCode::EmptySource | ApiErrorCode::InvalidSource | ApiErrorCode::BadRequest) => {
+            Error::InvalidParameters { message, context }
+        }
+        Some(ApiErrorCode::ImageTooLarge) => Error::ImageTooLarge {
+            size: None,
+            max_size: None,
+            context,
+        },
+        Some(ApiErrorCode::UnsupportedFormat) => Error::UnsupportedFormat {
+            format: None,
+            context,
+        },
+        Some(ApiErrorCode::RateLimited) => Error::RateLimitExceeded { context },
+        Some(code) => Error::ApiError {
+            message,
+            status: context.as_ref().map(|context| context.status),
+            code: Some(code),
+            context,
+        },
+        None => from_status(message, context),
+    }
+}
+
+/// Classify a failed response that did not carry an error code
+fn from_status(message: String, context: Option<Box<ResponseContext>>) -> Error {
+    match context.as_ref().map(|context| context.status) {
+        Some(429) => Error::RateLimitExceeded { context },
+        Some(413) => Error::ImageTooLarge {
+            size: None,
+            max_size: None,
+            context,
+        },
+        status => Error::ApiError {
+            message,
+            status,
+            code: None,
+            context,
+        },
+    }
+}
+
+/// Parse a response body and check it for errors
+///
+/// Returns `None` if the body is not JSON and the status is a success.
+/// A response fails if it carries an error object, has `success: false`,
+/// or has an error status.
+fn interpret(
+    kind: RequestKind,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<Option<Response>, Error> {
+    let context = || ResponseContext::new(kind, status, headers, body);
+
+    let mut response = match serde_json::from_str::<Response>(body) {
+        Ok(response) => response,
+        Err(_) if status.is_success() => return Ok(None),
+        Err(_) => {
+            return Err(from_status(
+                "Failed to parse response".to_string(),
+                Some(Box::new(context())),
+            ))
+        }
+    };
+
+    if let Some(error) = response.error.take() {
+        return Err(map_api_error(error, context()));
+    }
+
+    if response.success == Some(false) || !status.is_success() {
+        return Err(from_status(
+            format!("The {} request failed without specific error", kind),
+            Some(Box::new(context())),
+        ));
+    }
+
+    Ok(Some(response))
+}
+
+/// Interpret the body of an upload response
+///
+/// Uploads must return a JSON body with either `success: true` or the
+/// uploaded image data.
+pub(crate) fn parse_upload_response(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<Response, Error> {
+    let kind = RequestKind::Upload;
+
+    match interpret(kind, status, headers, body)? {
+        Some(response) if response.success == Some(true) || response.data.is_some() => Ok(response),
+        Some(_) => Err(from_status(
+            "The upload request returned no image data".to_string(),
+            Some(Box::new(ResponseContext::new(kind, status, headers, body))),
+        )),
+        None => Err(from_status(
+            "Failed to parse response".to_string(),
+            Some(Box::new(ResponseContext::new(kind, status, headers, body))),
+        )),
+    }
+}
+
+/// Interpret the body of a delete response
+///
+/// A successful status is enough for deletes, as the endpoint does not
+/// always answer with JSON.
+pub(crate) fn parse_delete_response(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<(), Error> {
+    interpret(RequestKind::Delete, status, headers, body).map(|_| ())
+}
+
+/// Interpret the response to a `HEAD` request for an uploaded image
+///
+/// Returns whether the image still exists. `404 Not Found` and `410 Gone`
+/// mean it does not, any other failed status is an error.
+pub(crate) fn parse_check_response(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<bool, Error> {
+    match status {
+        StatusCode::NOT_FOUND | StatusCode::GONE => Ok(false),
+        status if status.is_success() => Ok(true),
+        status => Err(from_status(
+            "The check request failed".to_string(),
+            Some(Box::new(ResponseContext::new(RequestKind::Check, status, headers, body))),
+        )),
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+
+use crate::{Error, RateLimiter};
+
+/// Predicate deciding whether an error is worth another attempt
+type RetryPredicate = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// Policy for retrying failed requests with exponential backoff
+///
+/// Attempt `n` (starting at 1) that fails with a retryable error is followed
+/// by a delay of `base_delay * 2^(n - 1)`, capped at `max_delay`. With jitter
+/// enabled, the delay is randomized between half and the full value.
+///
+/// When the server sends a `Retry-After` header, its value is used instead of
+/// the computed delay. If it asks for a longer wait than `max_delay`, the
+/// error is returned without retrying.
+///
+/// By default, errors for which [Error::is_retryable](Error::is_retryable)
+/// returns true are retried: connection failures, timeouts, 5xx responses and
+/// [Error::RateLimitExceeded](Error::RateLimitExceeded).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::{ImgBB, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let imgbb = ImgBB::builder("your_api_key")
+///     .retry(
+///         RetryPolicy::new()
+///             .max_attempts(5)
+///             .base_delay(Duration::from_millis(250))
+///             .max_delay(Duration::from_secs(10)),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retryable: Option<RetryPredicate>,
+}
+
+impl RetryPolicy {
+    /// Creates a policy with 3 attempts, a 500 ms base delay, a 30 s maximum
+    /// delay and jitter enabled
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retryable: None,
+        }
+    }
+
+    /// Creates a policy that never retries
+    ///
+    /// This is the policy used by clients that were not given one.
+    pub fn disabled() -> Self {
+        Self::new().max_attempts(1)
+    }
+
+    /// Set the maximum number of attempts, including the first one
+    ///
+    /// Values below 1 are treated as 1.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay after the first failed attempt
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Set the upper bound for any delay between attempts
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Enable or disable randomized delays
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set which errors are retried
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use imgbb::{Error, RetryPolicy};
+    ///
+    /// // Only retry when rate limited
+    /// let policy = RetryPolicy::new()
+    ///     .retry_if(|error| matches!(error, Error::RateLimitExceeded { .. }));
+    /// ```
+    pub fn retry_if<F>(mut self, retryable: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.retryable = Some(Arc::new(retryable));
+        self
+    }
+
+    /// Whether the policy considers the error worth another attempt
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match &self.retryable {
+            Some(retryable) => retryable(error),
+            None => error.is_retryable(),
+        }
+    }
+
+    /// Delay before the attempt following `attempt`, or `None` to give up
+    fn next_delay(
+        &self,
+        attempt: u32,
+        error: &Error,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.is_retryable(error) {
+            return None;
+        }
+
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_delay).then_some(retry_after);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        if self.jitter {
+            let half = delay / 2;
+            Some(half + half.mul_f64(fastrand::f64()))
+        } else {
+            Some(delay)
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("custom_retryable", &self.retryable.is_some())
+            .finish()
+    }
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Send a request, retrying according to the policy
+///
+/// `request` builds a fresh request for every attempt and `interpret` turns
+/// the status, headers and body of a response into the result. Every attempt waits
+/// for a token from the rate limiter, if any.
+pub(crate) async fn execute<T, R, I>(
+    policy: &RetryPolicy,
+    limiter: Option<&RateLimiter>,
+    mut request: R,
+    interpret: I,
+) -> Result<T, Error>
+where
+    R: FnMut() -> Result<reqwest::RequestBuilder, Error>,
+    I: Fn(StatusCode, &HeaderMap, &str) -> Result<T, Error>,
+{
+    let mut attempt = 1;
+
+    loop {
+        let request = request()?;
+
+        if let Some(limiter) = limiter {
+            limiter.acquire().await;
+        }
+
+        let (result, retry_after) = match request.send().await {
+            Ok(res) => {
+                let status = res.status();
+                let headers = res.headers().clone();
+                let retry_after = retry_after(&headers);
+                let result = match res.text().await {
+                    Ok(body) => interpret(status, &headers, &body),
+                    Err(e) => Err(Error::from(e)),
+                };
+                (result, retry_after)
+            }
+            Err(e) => (Err(Error::from(e)), None),
+        };
+
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        match policy.next_delay(attempt, &error, retry_after) {
+            Some(delay) => tokio::time::sleep(delay).await,
+            None => return Err(error),
+        }
+
+        attempt += 1;
+    }
+}
+
+/// Blocking counterpart of [execute]
+#[cfg(feature = "blocking")]
+pub(crate) fn execute_blocking<T, R, I>(
+    policy: &RetryPolicy,
+    limiter: Option<&RateLimiter>,
+    mut request: R,
+    interpret: I,
+) -> Result<T, Error>
+where
+    R: FnMut() -> Result<reqwest::blocking::RequestBuilder, Error>,
+    I: Fn(StatusCode, &HeaderMap, &str) -> Result<T, Error>,
+{
+    let mut attempt = 1;
+
+    loop {
+        let request = request()?;
+
+        if let Some(limiter) = limiter {
+            limiter.acquire_blocking();
+        }
+
+        let (result, retry_after) = match request.send() {
+            Ok(res) => {
+                let status = res.status();
+                let headers = res.headers().clone();
+                let retry_after = retry_after(&headers);
+                let result = match res.text() {
+                    Ok(body) => interpret(status, &headers, &body),
+                    Err(e) => Err(Error::from(e)),
+                };
+                (result, retry_after)
+            }
+            Err(e) => (Err(Error::from(e)), None),
+        };
+
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        match policy.next_delay(attempt, &error, retry_after) {
+            Some(delay) => std::thread::sleep(delay),
+            None => return Err(error),
+        }
+
+        attempt += 1;
+    }
+}
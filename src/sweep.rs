@@ -0,0 +1,202 @@
+use std::time::{Duration, SystemTime};
+
+use crate::dedupe::{self, DedupeCache};
+use crate::ledger::{EntryStatus, LedgerEntry};
+use crate::{Error, UploadLedger};
+
+/// Policy for reconciling a ledger with ImgBB using
+/// [ImgBB::sweep](crate::ImgBB::sweep)
+///
+/// A sweep always marks the entries whose expiration has passed as
+/// [EntryStatus::Expired]. It can also delete the images uploaded longer
+/// ago than a retention period, and check that the remaining images still
+/// exist with a `HEAD` request to their URL.
+///
+/// Only [EntryStatus::Active] entries are swept.
+///
+/// # Examples
+///
+/// ```rust
+/// use imgbb::SweepPolicy;
+/// use std::time::Duration;
+///
+/// // Delete images after 30 days and check the others
+/// let policy = SweepPolicy::new()
+///     .retention(Duration::from_secs(30 * 24 * 60 * 60))
+///     .check_remote(true);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SweepPolicy {
+    check_remote: bool,
+    retention: Option<Duration>,
+}
+
+impl SweepPolicy {
+    /// Creates a policy that only marks expired entries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether the images that have not expired still exist
+    ///
+    /// Images answered with `404 Not Found` or `410 Gone` are marked as
+    /// [EntryStatus::Missing], and their response is removed from the
+    /// client's dedupe cache.
+    pub fn check_remote(mut self, check_remote: bool) -> Self {
+        self.check_remote = check_remote;
+        self
+    }
+
+    /// Delete the images uploaded at least `retention` ago
+    ///
+    /// Deleted images are removed from the ledger and from the client's
+    /// dedupe cache.
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+}
+
+/// What a sweep changed in the ledger
+#[derive(Debug, Default)]
+pub struct SweepReport {
+    /// Entries marked as expired
+    pub expired: Vec<LedgerEntry>,
+    /// Entries marked as missing
+    pub missing: Vec<LedgerEntry>,
+    /// Entries whose image was deleted, removed from the ledger
+    pub deleted: Vec<LedgerEntry>,
+    /// Number of images found on ImgBB when checking
+    pub verified: usize,
+    /// Entries that could not be checked, deleted or updated in the ledger
+    ///
+    /// Entries whose image was deleted but could not be removed from the
+    /// ledger are also listed in [deleted](SweepReport::deleted).
+    pub failures: Vec<(LedgerEntry, Error)>,
+}
+
+impl SweepReport {
+    /// Whether the sweep changed the ledger
+    pub fn has_changes(&self) -> bool {
+        !(self.expired.is_empty() && self.missing.is_empty() && self.deleted.is_empty())
+    }
+
+    /// Record an entry under a new status
+    ///
+    /// Entries that cannot be recorded are listed as failures.
+    pub(crate) fn mark(&mut self, ledger: &dyn UploadLedger, mut entry: LedgerEntry, status: EntryStatus) {
+        entry.status = status;
+        if let Err(e) = ledger.record(&entry) {
+            self.failures.push((entry, e));
+            return;
+        }
+
+        match status {
+            EntryStatus::Expired => self.expired.push(entry),
+            EntryStatus::Missing => self.missing.push(entry),
+            EntryStatus::Active => {}
+        }
+    }
+
+    /// Apply the result of deleting the image of an entry
+    ///
+    /// Deleted entries are removed from the ledger by
+    /// [forget_deleted](SweepReport::forget_deleted).
+    pub(crate) fn deleted(
+        &mut self,
+        cache: Option<&dyn DedupeCache>,
+        entry: LedgerEntry,
+        result: Result<(), Error>,
+    ) {
+        match result {
+            Ok(()) => {
+                dedupe::evict(cache, entry.hash.as_ref());
+                self.deleted.push(entry);
+            }
+            Err(e) => self.failures.push((entry, e)),
+        }
+    }
+
+    /// Apply the result of checking whether the image of an entry exists
+    pub(crate) fn checked(
+        &mut self,
+        ledger: &dyn UploadLedger,
+        cache: Option<&dyn DedupeCache>,
+        entry: LedgerEntry,
+        result: Result<bool, Error>,
+    ) {
+        match result {
+            Ok(true) => self.verified += 1,
+            Ok(false) => {
+                dedupe::evict(cache, entry.hash.as_ref());
+                self.mark(ledger, entry, EntryStatus::Missing);
+            }
+            Err(e) => self.failures.push((entry, e)),
+        }
+    }
+
+    /// Remove the deleted entries from the ledger in one batch
+    ///
+    /// If the batch fails, the entries are removed one at a time, and the
+    /// ones left in the ledger are listed as failures as well as deleted.
+    pub(crate) fn forget_deleted(&mut self, ledger: &dyn UploadLedger) {
+        let ids: Vec<&str> = self.deleted.iter().map(|entry| entry.id.as_str()).collect();
+        if ids.is_empty() || ledger.remove(&ids).is_ok() {
+            return;
+        }
+
+        for entry in &self.deleted {
+            if let Err(e) = ledger.remove(&[entry.id.as_str()]) {
+                self.failures.push((entry.clone(), e));
+            }
+        }
+    }
+}
+
+/// What a sweep does with an entry
+pub(crate) enum Step {
+    Skip,
+    MarkExpired,
+    Delete,
+    Check,
+}
+
+/// Decide what to do with an entry at `now`
+///
+/// Expired images cannot be deleted or checked, so expiration comes first.
+pub(crate) fn plan(entry: &LedgerEntry, policy: &SweepPolicy, now: SystemTime) -> Step {
+    if entry.status != EntryStatus::Active {
+        return Step::Skip;
+    }
+
+    if entry.is_expired(now) {
+        return Step::MarkExpired;
+    }
+
+    let retained_until = policy
+        .retention
+        .and_then(|retention| entry.uploaded_at().checked_add(retention));
+    if retained_until.is_some_and(|retained_until| retained_until <= now) {
+        return Step::Delete;
+    }
+
+    if policy.check_remote {
+        Step::Check
+    } else {
+        Step::Skip
+    }
+}
+
+/// URL checked for a recorded image
+pub(crate) fn check_url(entry: &LedgerEntry) -> Result<&str, Error> {
+    entry
+        .url
+        .as_deref()
+        .or(entry.display_url.as_deref())
+        .ok_or_else(|| Error::invalid_parameters(format!("No URL recorded for image {}", entry.id)))
+}
+
+/// The client's ledger, which a sweep requires
+pub(crate) fn require_ledger(ledger: Option<&dyn UploadLedger>) -> Result<&dyn UploadLedger, Error> {
+    ledger.ok_or_else(|| Error::invalid_parameters("The client has no ledger to sweep"))
+}
@@ -0,0 +1,323 @@
+use base64::engine::{general_purpose, Engine};
+use bytes::Bytes;
+use futures_util::stream::{self, Stream, StreamExt};
+use std::borrow::Cow;
+use std::pin::Pin;
+
+use crate::progress::{chunked, ProgressTracker};
+
+#[cfg(feature = "blocking")]
+use crate::Error;
+
+/// Raw size (in bytes) at or above which [Transport::Auto] switches to multipart
+pub const MULTIPART_THRESHOLD: usize = 1024 * 1024;
+
+/// Chunk size used when in-memory bodies are streamed to report progress
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How image data is sent to ImgBB
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use imgbb::{ImgBB, Transport};
+///
+/// async fn example() -> Result<(), imgbb::Error> {
+///     let imgbb = ImgBB::new("your_api_key");
+///
+///     let image = imgbb.upload_builder()
+///         .file("path/to/screenshot.png")?
+///         .transport(Transport::Multipart)
+///         .upload()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Transport {
+    /// Use multipart for streams and inputs of at least [MULTIPART_THRESHOLD]
+    /// bytes, form encoding otherwise
+    #[default]
+    Auto,
+    /// Base64 encode the image and send it as `application/x-www-form-urlencoded`
+    Form,
+    /// Send the raw bytes as a file part of a `multipart/form-data` body
+    Multipart,
+}
+
+/// Boxed stream of image bytes
+pub(crate) type ByteStream = Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>>;
+
+/// A single field of a multipart body
+pub(crate) enum Part {
+    /// Plain text field
+    Text(String),
+    /// Binary file field
+    File {
+        data: Bytes,
+        filename: String,
+        mime: &'static str,
+    },
+    /// Binary file field read from a stream
+    Stream {
+        stream: ByteStream,
+        length: Option<u64>,
+        filename: String,
+        mime: &'static str,
+    },
+}
+
+/// Request body of an upload
+pub(crate) enum RequestBody<'a> {
+    /// `application/x-www-form-urlencoded` fields
+    Form(Vec<(&'static str, Cow<'a, str>)>),
+    /// `application/x-www-form-urlencoded` body, encoded while it is sent
+    FormStream(ByteStream),
+    /// `multipart/form-data` fields
+    Multipart(Vec<(&'static str, Part)>),
+}
+
+/// Attach an upload body to an async request
+pub(crate) fn apply(
+    request: reqwest::RequestBuilder,
+    body: RequestBody<'_>,
+) -> reqwest::RequestBuilder {
+    match body {
+        RequestBody::Form(form) => request.form(&form),
+        RequestBody::FormStream(stream) => request
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(reqwest::Body::wrap_stream(stream)),
+        RequestBody::Multipart(parts) => request.multipart(multipart_form(parts)),
+    }
+}
+
+/// Attach an upload body to a blocking request
+///
+/// # Errors
+///
+/// Returns an error if the body is streamed, which only the async client supports
+#[cfg(feature = "blocking")]
+pub(crate) fn apply_blocking(
+    request: reqwest::blocking::RequestBuilder,
+    body: RequestBody<'_>,
+) -> Result<reqwest::blocking::RequestBuilder, Error> {
+    match body {
+        RequestBody::Form(form) => Ok(request.form(&form)),
+        RequestBody::FormStream(_) => Err(streaming_unsupported()),
+        RequestBody::Multipart(parts) => Ok(request.multipart(blocking_multipart_form(parts)?)),
+    }
+}
+
+/// Build an async reqwest multipart form from body parts
+fn multipart_form(parts: Vec<(&'static str, Part)>) -> reqwest::multipart::Form {
+    parts
+        .into_iter()
+        .fold(reqwest::multipart::Form::new(), |form, (name, part)| match part {
+            Part::Text(value) => form.text(name, value),
+            Part::File {
+                data,
+                filename,
+                mime,
+            } => form.part(name, file_part(data, filename, mime)),
+            Part::Stream {
+                stream,
+                length,
+                filename,
+                mime,
+            } => {
+                let body = reqwest::Body::wrap_stream(stream);
+                let part = match length {
+                    Some(length) => reqwest::multipart::Part::stream_with_length(body, length),
+                    None => reqwest::multipart::Part::stream(body),
+                };
+                form.part(name, with_file_name(part, filename, mime))
+            }
+        })
+}
+
+/// Build a blocking reqwest multipart form from body parts
+#[cfg(feature = "blocking")]
+fn blocking_multipart_form(
+    parts: Vec<(&'static str, Part)>,
+) -> Result<reqwest::blocking::multipart::Form, Error> {
+    parts.into_iter().try_fold(
+        reqwest::blocking::multipart::Form::new(),
+        |form, (name, part)| match part {
+            Part::Text(value) => Ok(form.text(name, value)),
+            Part::File {
+                data,
+                filename,
+                mime,
+            } => {
+                let length = data.len() as u64;
+                let part = reqwest::blocking::multipart::Part::reader_with_length(
+                    std::io::Cursor::new(data),
+                    length,
+                )
+                .file_name(filename)
+                .mime_str(mime)
+                .expect("static MIME types are valid");
+                Ok(form.part(name, part))
+            }
+            Part::Stream { .. } => Err(streaming_unsupported()),
+        },
+    )
+}
+
+#[cfg(feature = "blocking")]
+fn streaming_unsupported() -> Error {
+    Error::invalid_parameters("Streaming sources require the async client")
+}
+
+/// Build an async reqwest file part from raw bytes
+pub(crate) fn file_part(data: Bytes, filename: String, mime: &str) -> reqwest::multipart::Part {
+    let length = data.len() as u64;
+
+    with_file_name(
+        reqwest::multipart::Part::stream_with_length(reqwest::Body::from(data), length),
+        filename,
+        mime,
+    )
+}
+
+fn with_file_name(
+    part: reqwest::multipart::Part,
+    filename: String,
+    mime: &str,
+) -> reqwest::multipart::Part {
+    part.file_name(filename)
+        .mime_str(mime)
+        .expect("static MIME types are valid")
+}
+
+/// Base64 encode raw bytes for form uploads
+pub(crate) fn encode(data: &[u8]) -> String {
+    general_purpose::STANDARD.encode(data)
+}
+
+/// Decode base64 data for multipart uploads, if it is valid
+pub(crate) fn decode(data: &str) -> Option<Bytes> {
+    general_purpose::STANDARD.decode(data).ok().map(Bytes::from)
+}
+
+/// Base64 and url-encode a streamed image into a form body
+///
+/// `prefix` holds the already encoded metadata fields and must end with `image=`.
+/// Input chunks are encoded in groups of three bytes so no padding appears
+/// before the end of the stream.
+pub(crate) fn form_stream(
+    prefix: String,
+    image: ByteStream,
+    progress: Option<ProgressTracker>,
+) -> ByteStream {
+    let encoded = stream::unfold(Some((image, Vec::new())), move |state| {
+        let progress = progress.clone();
+        async move {
+            let (mut image, mut rest) = state?;
+
+            let (out, next) = match image.next().await {
+                Some(Ok(chunk)) => {
+                    rest.extend_from_slice(&chunk);
+                    let complete = rest.len() / 3 * 3;
+                    let out = form_encode(&rest[..complete]);
+                    rest.drain(..complete);
+                    if let Some(progress) = &progress {
+                        progress.encoded(chunk.len());
+                    }
+                    (out, Some((image, rest)))
+                }
+                Some(Err(e)) => return Some((Err(e), None)),
+                None => (form_encode(&rest), None),
+            };
+
+            if let Some(progress) = &progress {
+                progress.sent(out.len());
+            }
+            Some((Ok(Bytes::from(out)), next))
+        }
+    });
+
+    Box::pin(stream::once(async move { Ok(Bytes::from(prefix)) }).chain(encoded))
+}
+
+/// Form body with an already encoded image value, streamed in chunks so
+/// progress can be reported
+pub(crate) fn counted_form(prefix: String, image: String, progress: &ProgressTracker) -> ByteStream {
+    let image = progress.count_sent(chunked(Bytes::from(image), CHUNK_SIZE));
+
+    Box::pin(stream::once(async move { Ok(Bytes::from(prefix)) }).chain(image))
+}
+
+/// Multipart file data streamed in chunks so progress can be reported
+pub(crate) fn counted_file(data: Bytes, progress: &ProgressTracker) -> ByteStream {
+    progress.count_raw(chunked(data, CHUNK_SIZE))
+}
+
+/// Url-encode form fields, ending with the key of the image field
+pub(crate) fn form_prefix(metadata: &[(&'static str, String)]) -> String {
+    let mut prefix = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(metadata)
+        .finish();
+    if !prefix.is_empty() {
+        prefix.push('&');
+    }
+    prefix.push_str("image=");
+    prefix
+}
+
+/// Escape a value for a url-encoded form
+pub(crate) fn form_escape(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// Base64 encode bytes and escape the result for a url-encoded form value
+pub(crate) fn form_encode(data: &[u8]) -> String {
+    encode(data)
+        .replace('+', "%2B")
+        .replace('/', "%2F")
+        .replace('=', "%3D")
+}
+
+/// Whether data of the given raw size should be sent as multipart
+pub(crate) fn use_multipart(transport: Transport, raw_len: Option<usize>) -> bool {
+    match transport {
+        Transport::Form => false,
+        Transport::Multipart => true,
+        Transport::Auto => raw_len.is_some_and(|len| len >= MULTIPART_THRESHOLD),
+    }
+}
+
+/// Raw size of base64 encoded data, accounting for `=` padding
+///
+/// Exact for valid base64, with or without padding.
+pub(crate) fn decoded_len(data: &str) -> usize {
+    let data = data.trim_end();
+    let padding = data.bytes().rev().take(2).take_while(|&byte| byte == b'=').count();
+    let len = data.len() - padding;
+
+    len / 4 * 3 + (len % 4 * 3) / 4
+}
+
+/// Guess the MIME type of an image from its file extension
+pub(crate) fn mime_from_filename(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "tif" | "tiff" => "image/tiff",
+        "heic" => "image/heic",
+        "avif" => "image/avif",
+        _ => "application/octet-stream",
+    }
+}
@@ -1,9 +1,21 @@
-use crate::Error;
-use crate::Response;
+use std::path::PathBuf;
+use std::sync::Arc;
 
-const URL: &str = "https://api.imgbb.com/1/upload";
+use crate::dedupe::DedupeCache;
+use crate::ledger::UploadLedger;
+use crate::retry::RetryPolicy;
+use crate::transport::Transport;
+use crate::{Error, Expiration};
+use crate::{ImageSource, RateLimiter, UploadOptions, UploaderBuilder};
+use crate::Response;
+use crate::{DEFAULT_BASE_URL, DEFAULT_MAX_IMAGE_SIZE};
 
 /// An struct that holds the data (base64) to be uploaded
+///
+/// Besides the public fields, the base URL, transport, retry policy, rate
+/// limiter, size limit, dedupe cache and ledger are set through their
+/// setters, or inherited from the [ImgBB](crate::ImgBB) client that created
+/// the uploader.
 pub struct Uploader<'a> {
     /// ImgBB API key
     pub api_key: String,
@@ -11,6 +23,18 @@ pub struct Uploader<'a> {
     pub data: Option<String>,
     /// Expiration time in seconds
     pub expiration: Option<u64>,
+    pub(crate) base_url: String,
+    pub(crate) transport: Transport,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) max_image_size: u64,
+    pub(crate) dedupe: Option<Arc<dyn DedupeCache>>,
+    pub(crate) ledger: Option<Arc<dyn UploadLedger>>,
+    /// Path of the file the data was read from, recorded in the ledger
+    pub(crate) source_path: Option<PathBuf>,
+    /// Raw size of bytes read over the size limit, left unencoded and
+    /// reported when uploading
+    pub(crate) oversized: Option<u64>,
     /// HTTP client
     pub client: &'a reqwest::Client,
 }
@@ -25,68 +49,110 @@ impl<'a> Uploader<'a> {
             api_key: api_key.into(),
             data: None,
             expiration: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            transport: Transport::default(),
+            retry: RetryPolicy::disabled(),
+            rate_limiter: None,
+            max_image_size: DEFAULT_MAX_IMAGE_SIZE,
+            dedupe: None,
+            ledger: None,
+            source_path: None,
+            oversized: None,
             client,
         }
     }
 
-    /// Set [expiration time](Uploader::expiration)
-    pub fn expiration(&mut self, expiration: u64) -> &Self {
-        self.expiration = Some(expiration);
+    /// Set [expiration time](Uploader::expiration), in seconds or as a
+    /// [Duration](std::time::Duration)
+    pub fn expiration<T>(&mut self, expiration: T) -> &Self
+    where
+        T: Into<Expiration>,
+    {
+        self.expiration = Some(expiration.into().as_secs());
         self
     }
 
-    /// Upload [data](Uploader::data) to ImgBB
-    pub async fn upload(&self) -> Result<Response, Error> {
-        let mut query = vec![("key", self.api_key.as_str())];
+    /// Set the base URL of the ImgBB API
+    pub fn base_url<T>(&mut self, base_url: T) -> &Self
+    where
+        T: Into<String>,
+    {
+        self.base_url = base_url.into();
+        self
+    }
 
-        let exp_str = self.expiration.as_ref().unwrap_or(&0).to_string();
-        if self.expiration.is_some() {
-            query.push(("expiration", exp_str.as_str()));
-        }
+    /// Set how the data is sent to ImgBB
+    ///
+    /// With multipart, the base64 [data](Uploader::data) is decoded and sent
+    /// as raw bytes.
+    pub fn transport(&mut self, transport: Transport) -> &Self {
+        self.transport = transport;
+        self
+    }
 
-        if self.data.is_none() {
-            return Err(Error::InvalidParameters("Missing image data".to_string()));
-        }
+    /// Set the policy for retrying failed uploads
+    pub fn retry(&mut self, retry: RetryPolicy) -> &Self {
+        self.retry = retry;
+        self
+    }
 
-        let form = [("image", self.data.as_ref().unwrap().as_str())];
+    /// Set the rate limiter to wait on before each request
+    pub fn rate_limiter(&mut self, rate_limiter: RateLimiter) -> &Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
 
-        let res = self.client
-            .post(URL)
-            .query(&query)
-            .form(&form)
-            .send()
-            .await?;
+    /// Set the largest raw image size, in bytes, that is sent
+    pub fn max_image_size(&mut self, max_image_size: u64) -> &Self {
+        self.max_image_size = max_image_size;
+        self
+    }
 
-        let status = res.status().as_u16();
-        let response: Response = res.json().await?;
+    /// Set the cache of earlier uploads, looked up before sending
+    pub fn dedupe_cache(&mut self, cache: Arc<dyn DedupeCache>) -> &Self {
+        self.dedupe = Some(cache);
+        self
+    }
 
-        if let Some(error) = response.error {
-            let error_code = error.code.unwrap_or(0);
-            let error_message = error.message.unwrap_or_else(|| "Unknown error".to_string());
-            
-            return match error_code {
-                100 => Err(Error::InvalidApiKey),
-                120 => Err(Error::InvalidBase64Data),
-                313 => Err(Error::ImageTooLarge),
-                400 => Err(Error::InvalidParameters(error_message)),
-                401 => Err(Error::UnsupportedFormat),
-                429 => Err(Error::RateLimitExceeded),
-                _ => Err(Error::ApiError {
-                    message: error_message,
-                    status: Some(status),
-                    code: Some(error_code),
-                }),
-            };
-        }
+    /// Set the ledger successful uploads are recorded in
+    pub fn ledger(&mut self, ledger: Arc<dyn UploadLedger>) -> &Self {
+        self.ledger = Some(ledger);
+        self
+    }
 
-        if response.success != Some(true) {
-            return Err(Error::ApiError {
-                message: "Upload failed without specific error".to_string(),
-                status: Some(status),
-                code: None,
-            });
-        }
+    /// Upload [data](Uploader::data) to ImgBB
+    ///
+    /// Images over the [size limit](Uploader::max_image_size) or in a format
+    /// ImgBB does not accept are rejected before sending. With a
+    /// [dedupe cache](Uploader::dedupe_cache), the cached response of an
+    /// identical image is returned without sending it.
+    ///
+    /// The upload goes through the same pipeline as
+    /// [UploaderBuilder](crate::UploaderBuilder).
+    pub async fn upload(&self) -> Result<Response, Error> {
+        let data = match (&self.data, self.oversized) {
+            (Some(data), _) => data.clone(),
+            (None, Some(size)) => return Err(Error::image_too_large(size, self.max_image_size)),
+            (None, None) => return Err(Error::invalid_parameters("Missing image data")),
+        };
+
+        let mut options = UploadOptions::new(self.max_image_size);
+        options.set_data(ImageSource::Base64(data));
+        options.source_path = self.source_path.clone();
+        options.expiration = self.expiration.map(Expiration::from);
+        options.transport = self.transport;
 
-        Ok(response)
+        UploaderBuilder {
+            api_key: self.api_key.clone(),
+            options,
+            base_url: self.base_url.clone(),
+            retry: self.retry.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            dedupe: self.dedupe.clone(),
+            ledger: self.ledger.clone(),
+            client: self.client.clone(),
+        }
+        .send()
+        .await
     }
 }
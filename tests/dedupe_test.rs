@@ -0,0 +1,125 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use imgbb::dedupe::{ContentHash, DedupeCache, JsonFileCache, MemoryCache};
+use imgbb::model::Response;
+
+// Tests for the dedupe caches
+
+/// Upload response of an image uploaded at `time` with the given lifetime
+fn response(id: &str, time: u64, expiration: u64) -> Response {
+    serde_json::from_value(serde_json::json!({
+        "data": {
+            "id": id,
+            "url": format!("https://i.ibb.co/{}/image.png", id),
+            "time": time,
+            "expiration": expiration,
+        },
+        "success": true,
+        "status": 200,
+    }))
+    .unwrap()
+}
+
+fn id(response: Option<Response>) -> Option<String> {
+    response.and_then(|response| response.data?.id)
+}
+
+/// Store a permanent, a live and an expired response, then evict at `now`
+fn exercise(cache: &dyn DedupeCache, now: SystemTime) {
+    let now_secs = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let permanent = ContentHash::of(b"permanent");
+    let live = ContentHash::of(b"live");
+    let expired = ContentHash::of(b"expired");
+
+    cache.insert(permanent, &response("permanent", 1_552_042_565, 0)).unwrap();
+    cache.insert(live, &response("live", now_secs - 60, 600)).unwrap();
+    cache.insert(expired, &response("expired", now_secs - 600, 60)).unwrap();
+
+    assert_eq!(id(cache.get(&live).unwrap()).as_deref(), Some("live"));
+    assert_eq!(id(cache.get(&ContentHash::of(b"other")).unwrap()), None);
+
+    assert_eq!(cache.evict_expired(now).unwrap(), 1);
+    assert_eq!(id(cache.get(&expired).unwrap()), None);
+    assert_eq!(id(cache.get(&permanent).unwrap()).as_deref(), Some("permanent"));
+
+    // The live image expires later
+    assert_eq!(cache.evict_expired(now + Duration::from_secs(600)).unwrap(), 1);
+    assert_eq!(id(cache.get(&live).unwrap()), None);
+
+    cache.remove(&permanent).unwrap();
+    assert_eq!(id(cache.get(&permanent).unwrap()), None);
+}
+
+#[test]
+fn test_content_hash_is_sha256_hex() {
+    let hash = ContentHash::of(b"abc");
+
+    assert_eq!(
+        hash.to_string(),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(hash.to_string().parse::<ContentHash>().unwrap(), hash);
+    assert!("ba7816bf".parse::<ContentHash>().is_err());
+    assert!("zz".repeat(32).parse::<ContentHash>().is_err());
+}
+
+#[test]
+fn test_memory_cache() {
+    let cache = MemoryCache::new();
+    exercise(&cache, SystemTime::now());
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_json_file_cache_persists_entries() {
+    let path = std::env::temp_dir().join("imgbb_dedupe_test.json");
+    let _ = std::fs::remove_file(&path);
+
+    let cache = JsonFileCache::open(&path).unwrap();
+    exercise(&cache, SystemTime::now());
+
+    let hash = ContentHash::of(b"image");
+    cache.insert(hash, &response("2ndCYJK", 1_552_042_565, 0)).unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(json[hash.to_string()]["data"]["id"], "2ndCYJK");
+
+    let reopened = JsonFileCache::open(&path).unwrap();
+    assert_eq!(id(reopened.get(&hash).unwrap()).as_deref(), Some("2ndCYJK"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_json_file_cache_rejects_invalid_files() {
+    let path = std::env::temp_dir().join("imgbb_dedupe_invalid.json");
+    std::fs::write(&path, "not json").unwrap();
+
+    let result = JsonFileCache::open(&path);
+    assert!(matches!(result, Err(imgbb::Error::IOError(_))), "{:?}", result);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_cache_persists_entries() {
+    use imgbb::dedupe::SqliteCache;
+
+    exercise(&SqliteCache::open_in_memory().unwrap(), SystemTime::now());
+
+    let path = std::env::temp_dir().join("imgbb_dedupe_test.db");
+    let _ = std::fs::remove_file(&path);
+
+    let hash = ContentHash::of(b"image");
+    SqliteCache::open(&path)
+        .unwrap()
+        .insert(hash, &response("2ndCYJK", 1_552_042_565, 0))
+        .unwrap();
+
+    let reopened = SqliteCache::open(&path).unwrap();
+    assert_eq!(id(reopened.get(&hash).unwrap()).as_deref(), Some("2ndCYJK"));
+
+    drop(reopened);
+    std::fs::remove_file(&path).unwrap();
+}
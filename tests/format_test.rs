@@ -0,0 +1,71 @@
+use base64::Engine;
+use imgbb::ImageFormat;
+
+// Tests for detecting image formats from their magic bytes
+
+#[test]
+fn test_detects_supported_formats() {
+    let cases: [(&[u8], ImageFormat); 10] = [
+        (b"\xFF\xD8\xFF\xE0\0\x10JFIF\0", ImageFormat::Jpeg),
+        (b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR", ImageFormat::Png),
+        (b"GIF89a\x01\0\x01\0", ImageFormat::Gif),
+        (b"BM\x46\0\0\0\0\0\0\0\x36\0\0\0", ImageFormat::Bmp),
+        (b"RIFF\x24\0\0\0WEBPVP8 ", ImageFormat::Webp),
+        (b"II*\0\x08\0\0\0", ImageFormat::Tiff),
+        (b"MM\0*\0\0\0\x08", ImageFormat::Tiff),
+        (b"\0\0\0\x18ftypheic\0\0\0\0mif1heic", ImageFormat::Heic),
+        (b"\0\0\0\x1Cftypavif\0\0\0\0avifmif1miaf", ImageFormat::Avif),
+        // AVIF files may use the generic HEIF brand as the major brand
+        (b"\0\0\0\x1Cftypmif1\0\0\0\0mif1avifmiaf", ImageFormat::Avif),
+    ];
+
+    for (data, expected) in cases {
+        let format = ImageFormat::detect(data);
+        assert_eq!(format, Some(expected), "{:?}", data);
+        assert!(expected.is_supported());
+    }
+}
+
+#[test]
+fn test_detects_unsupported_formats() {
+    let cases: [(&[u8], ImageFormat); 5] = [
+        (b"\0\0\x01\0\x01\0\x10\x10", ImageFormat::Ico),
+        (b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>", ImageFormat::Svg),
+        (b"\xEF\xBB\xBF<?xml version=\"1.0\"?>\n<svg/>", ImageFormat::Svg),
+        (b"8BPS\0\x01", ImageFormat::Psd),
+        (b"\xFF\x0A\xFA\x7F", ImageFormat::JpegXl),
+    ];
+
+    for (data, expected) in cases {
+        assert_eq!(ImageFormat::detect(data), Some(expected), "{:?}", data);
+        assert!(!expected.is_supported());
+    }
+}
+
+#[test]
+fn test_unknown_data() {
+    assert_eq!(ImageFormat::detect(b""), None);
+    assert_eq!(ImageFormat::detect(b"image"), None);
+    assert_eq!(ImageFormat::detect(b"This is not an image file"), None);
+    assert_eq!(ImageFormat::detect(b"\0\0\0\x18ftypisom\0\0\0\0isommp41"), None);
+}
+
+#[test]
+fn test_detects_base64_data() {
+    let mut png = b"\x89PNG\r\n\x1A\n".to_vec();
+    png.resize(1032, 0);
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&png);
+
+    assert_eq!(ImageFormat::detect_base64(&encoded), Some(ImageFormat::Png));
+    assert_eq!(ImageFormat::detect_base64(&encoded[..7]), None);
+    assert_eq!(ImageFormat::detect_base64("not_valid_base64_data"), None);
+}
+
+#[test]
+fn test_mime_types_and_extensions() {
+    assert_eq!(ImageFormat::Jpeg.mime_type(), "image/jpeg");
+    assert_eq!(ImageFormat::Jpeg.extension(), "jpg");
+    assert_eq!(ImageFormat::Heic.mime_type(), "image/heic");
+    assert_eq!(ImageFormat::Svg.mime_type(), "image/svg+xml");
+    assert_eq!(ImageFormat::JpegXl.to_string(), "JPEG XL");
+}
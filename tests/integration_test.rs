@@ -19,43 +19,30 @@ fn create_test_image() -> std::path::PathBuf {
         0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82
     ];
     
-    std::fs::write(&file_path, &png_data).unwrap();
+    std::fs::write(&file_path, png_data).unwrap();
     file_path
 }
 
 /// Validate common response fields that should be present in every upload response
-fn validate_response_integrity(data: &imgbb::model::Data) {
-    // Check essential fields existence
-    assert!(data.id.is_some(), "Image ID is missing");
-    assert!(data.url.is_some(), "Image URL is missing");
-    assert!(data.delete_url.is_some(), "Delete URL is missing");
-    
+fn validate_response_integrity(image: &imgbb::UploadedImage) {
     // Validate URL formats
-    if let Some(url) = &data.url {
-        assert!(url.starts_with("https://i.ibb.co/"), "Image URL has unexpected format");
-    }
+    assert!(image.url.starts_with("https://i.ibb.co/"), "Image URL has unexpected format");
     
-    if let Some(url_viewer) = &data.url_viewer {
+    if let Some(url_viewer) = &image.url_viewer {
         assert!(url_viewer.starts_with("https://ibb.co/"), "Viewer URL has unexpected format");
     }
     
-    if let Some(delete_url) = &data.delete_url {
-        assert!(delete_url.contains("ibb.co/"), "Delete URL has unexpected format");
-    }
+    assert!(image.delete_url.contains("ibb.co/"), "Delete URL has unexpected format");
     
     // Validate image dimensions (1x1 for our test image)
-    if let Some(width) = data.width {
-        assert_eq!(width, 1, "Unexpected image width");
-    }
-    
-    if let Some(height) = data.height {
-        assert_eq!(height, 1, "Unexpected image height");
-    }
-    
-    // Skip timestamp validation as it's complex to handle multiple formats
+    assert_eq!(image.width, 1, "Unexpected image width");
+    assert_eq!(image.height, 1, "Unexpected image height");
     
+    // Validate the upload timestamp, which may be sent as a number or a string
+    assert!(image.time.is_some_and(|time| time > 0), "Upload time is missing");
+
     // Validate image details
-    if let Some(ref img) = data.image {
+    if let Some(ref img) = image.image {
         assert!(img.filename.is_some(), "Image filename is missing");
         assert!(img.name.is_some(), "Image name is missing");
         assert_eq!(img.mime.as_deref(), Some("image/png"), "Unexpected MIME type");
@@ -85,22 +72,15 @@ async fn test_real_upload_and_delete() {
     let imgbb = ImgBB::new(&api_key);
     
     // Test the upload
-    let response = imgbb.upload_file(&image_path).await.expect("Failed to upload image");
-    
-    // Verify we got a successful response
-    assert!(response.success.unwrap_or(false), "Upload was not successful");
-    assert!(response.data.is_some(), "Response data is missing");
-    
-    let data = response.data.unwrap();
+    let image = imgbb.upload_file(&image_path).await.expect("Failed to upload image");
     
     // Validate the response integrity
-    validate_response_integrity(&data);
+    validate_response_integrity(&image);
     
-    println!("Successfully uploaded image: {}", data.url.unwrap());
+    println!("Successfully uploaded image: {}", image.url);
     
     // Test deletion
-    let delete_url = data.delete_url.unwrap();
-    imgbb.delete(&delete_url).await.expect("Failed to delete image");
+    imgbb.delete(&image.delete_url).await.expect("Failed to delete image");
     
     println!("Successfully deleted image");
 }
@@ -124,7 +104,7 @@ async fn test_advanced_upload() {
     let imgbb = ImgBB::new(&api_key);
     
     // Create an advanced upload with options
-    let response = imgbb.upload_builder()
+    let image = imgbb.upload_builder()
         .file(&image_path).expect("Failed to read file")
         .name("test-image")  // Note: ImgBB API converts underscores to hyphens
         .title("Integration Test Image")
@@ -133,25 +113,17 @@ async fn test_advanced_upload() {
         .await
         .expect("Failed to upload image");
     
-    // Verify we got a successful response
-    assert!(response.success.unwrap_or(false), "Upload was not successful");
-    assert!(response.data.is_some(), "Response data is missing");
-    
-    let data = response.data.unwrap();
-    
     // Validate the response integrity
-    validate_response_integrity(&data);
+    validate_response_integrity(&image);
     
     // Just check that there's some expiration value
-    assert!(data.expiration.is_some(), "Expiration field should be present");
+    assert!(image.expiration.is_some(), "Expiration field should be present");
     
-    println!("Successfully uploaded image with options: {}", data.url.unwrap());
+    println!("Successfully uploaded image with options: {}", image.url);
     
     // Clean up by deleting the image
-    if let Some(delete_url) = data.delete_url {
-        imgbb.delete(&delete_url).await.expect("Failed to delete image");
-        println!("Successfully deleted image");
-    }
+    imgbb.delete(&image.delete_url).await.expect("Failed to delete image");
+    println!("Successfully deleted image");
 }
 
 // Test error handling for invalid API key
@@ -169,7 +141,7 @@ async fn test_invalid_api_key() {
     // It should return an InvalidApiKey error
     assert!(result.is_err(), "Expected error for invalid API key");
     match result {
-        Err(imgbb::Error::InvalidApiKey) => {
+        Err(imgbb::Error::InvalidApiKey { .. }) => {
             println!("Successfully detected invalid API key error");
         },
         Err(err) => {
@@ -203,7 +175,7 @@ async fn test_invalid_base64_data() {
 
     assert!(result.is_err(), "Expected error for invalid base64 data");
     match result {
-        Err(imgbb::Error::InvalidBase64Data) => {
+        Err(imgbb::Error::InvalidBase64Data { .. }) => {
             println!("Successfully detected invalid base64 data error");
         },
         Err(err) => {
@@ -238,7 +210,7 @@ async fn test_image_too_large() {
 
     assert!(result.is_err(), "Expected error for large image");
     match result {
-        Err(imgbb::Error::ImageTooLarge) => {
+        Err(imgbb::Error::ImageTooLarge { .. }) => {
             println!("Successfully detected image too large error");
         },
         Err(err) => {
@@ -273,7 +245,7 @@ async fn test_unsupported_format() {
 
     assert!(result.is_err(), "Expected error for unsupported format");
     match result {
-        Err(imgbb::Error::UnsupportedFormat) => {
+        Err(imgbb::Error::UnsupportedFormat { .. }) => {
             println!("Successfully detected unsupported format error");
         },
         Err(err) => {
@@ -305,7 +277,7 @@ async fn test_invalid_parameters() {
 
     assert!(result.is_err(), "Expected error for invalid parameters");
     match result {
-        Err(imgbb::Error::InvalidParameters(_)) => {
+        Err(imgbb::Error::InvalidParameters { .. }) => {
             println!("Successfully detected invalid parameters error");
         },
         Err(err) => {
@@ -343,7 +315,7 @@ async fn test_rate_limit() {
 
     // Check if any request hit the rate limit
     let rate_limited = results.iter().any(|result| {
-        matches!(result, Err(imgbb::Error::RateLimitExceeded))
+        matches!(result, Err(imgbb::Error::RateLimitExceeded { .. }))
     });
 
     if rate_limited {
@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use imgbb::dedupe::ContentHash;
+use imgbb::ledger::{EntryStatus, JsonLinesLedger};
+use imgbb::{LedgerEntry, UploadLedger};
+
+// Tests for the upload ledgers
+
+fn entry(id: &str, source: &str, time: u64) -> LedgerEntry {
+    LedgerEntry {
+        id: id.to_string(),
+        url: Some(format!("https://i.ibb.co/{}/image.png", id)),
+        url_viewer: Some(format!("https://ibb.co/{}", id)),
+        display_url: None,
+        delete_url: Some(format!("https://ibb.co/{}/delete", id)),
+        hash: Some(ContentHash::of(source.as_bytes())),
+        source_path: Some(PathBuf::from(source)),
+        name: None,
+        title: Some(format!("Image {}", id)),
+        album: None,
+        time,
+        expiration: None,
+        status: EntryStatus::Active,
+    }
+}
+
+fn ids(entries: Vec<LedgerEntry>) -> Vec<String> {
+    entries.into_iter().map(|entry| entry.id).collect()
+}
+
+/// Record a few entries, then run every query and remove some of them
+fn exercise(ledger: &dyn UploadLedger) {
+    ledger.record(&entry("a", "photos/cat.png", 1_000)).unwrap();
+    ledger.record(&entry("b", "photos/dog.png", 2_000)).unwrap();
+    ledger.record(&entry("c", "photos/cat.png", 3_000)).unwrap();
+
+    // Recording an ID again replaces its entry
+    let mut renamed = entry("b", "photos/dog.png", 2_000);
+    renamed.title = Some("Renamed".to_string());
+    ledger.record(&renamed).unwrap();
+
+    assert_eq!(ids(ledger.entries().unwrap()), ["a", "b", "c"]);
+    assert_eq!(ledger.find_by_id("b").unwrap(), Some(renamed));
+    assert_eq!(ledger.find_by_id("missing").unwrap(), None);
+
+    let cat = ContentHash::of(b"photos/cat.png");
+    assert_eq!(ids(ledger.find_by_hash(&cat).unwrap()), ["a", "c"]);
+    assert_eq!(ids(ledger.find_by_source(Path::new("photos/dog.png")).unwrap()), ["b"]);
+
+    let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+    assert_eq!(ids(ledger.find_between(at(1_000), at(3_000)).unwrap()), ["a", "b"]);
+    assert_eq!(ids(ledger.find_between(at(1_001), at(10_000)).unwrap()), ["b", "c"]);
+
+    assert_eq!(ledger.remove(&["a", "c", "missing"]).unwrap(), 2);
+    assert_eq!(ledger.remove(&[]).unwrap(), 0);
+    assert_eq!(ids(ledger.entries().unwrap()), ["b"]);
+}
+
+#[test]
+fn test_ledger_entry_expiration() {
+    let mut entry = entry("a", "cat.png", 1_000);
+    let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+
+    assert_eq!(entry.expires_at(), None);
+    assert!(!entry.is_expired(at(u32::MAX as u64)));
+
+    entry.expiration = Some(600);
+    assert_eq!(entry.uploaded_at(), at(1_000));
+    assert_eq!(entry.expires_at(), Some(at(1_600)));
+    assert!(!entry.is_expired(at(1_599)));
+    assert!(entry.is_expired(at(1_600)));
+}
+
+#[test]
+fn test_json_lines_ledger() {
+    let path = std::env::temp_dir().join("imgbb_ledger_test.jsonl");
+    let _ = std::fs::remove_file(&path);
+
+    let ledger = JsonLinesLedger::open(&path);
+    assert!(ledger.entries().unwrap().is_empty());
+    exercise(&ledger);
+
+    // One JSON object per line, readable by a new ledger
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().count(), 1);
+    let json: serde_json::Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+    assert_eq!(json["id"], "b");
+    assert_eq!(json["source_path"], "photos/dog.png");
+
+    let reopened = JsonLinesLedger::open(&path);
+    assert_eq!(ids(reopened.entries().unwrap()), ["b"]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_json_lines_ledger_compacts_replaced_entries() {
+    let path = std::env::temp_dir().join("imgbb_ledger_compact_test.jsonl");
+    let _ = std::fs::remove_file(&path);
+    let lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+    let ledger = JsonLinesLedger::open(&path);
+    ledger.record(&entry("a", "cat.png", 1_000)).unwrap();
+    for time in 0..1_000 {
+        ledger.record(&entry("b", "dog.png", time)).unwrap();
+    }
+
+    // Recording compacts the file once most lines were replaced
+    assert!(lines() < 200, "{} lines", lines());
+    let entries = ledger.entries().unwrap();
+    assert_eq!(ids(entries.clone()), ["a", "b"]);
+    assert_eq!(entries[1].time, 999);
+
+    // Removing nothing still drops the replaced lines
+    ledger.record(&entry("b", "dog.png", 1_000)).unwrap();
+    assert_eq!(ledger.remove(&["missing"]).unwrap(), 0);
+    assert_eq!(lines(), 2);
+    assert_eq!(ledger.find_by_id("b").unwrap().unwrap().time, 1_000);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_json_lines_ledger_records_when_compaction_fails() {
+    let path = std::env::temp_dir().join("imgbb_ledger_compact_failure_test.jsonl");
+    std::fs::write(&path, "not json\n").unwrap();
+
+    // The file cannot be read to compact it, but every entry is appended
+    let ledger = JsonLinesLedger::open(&path);
+    for time in 0..100 {
+        ledger.record(&entry("a", "cat.png", time)).unwrap();
+    }
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 101);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_entries_recorded_without_status_are_active() {
+    let mut json = serde_json::to_value(entry("a", "cat.png", 1_000)).unwrap();
+    assert_eq!(json["status"], "active");
+
+    json.as_object_mut().unwrap().remove("status");
+    let entry: LedgerEntry = serde_json::from_value(json).unwrap();
+    assert_eq!(entry.status, EntryStatus::Active);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_ledger() {
+    use imgbb::ledger::SqliteLedger;
+
+    exercise(&SqliteLedger::open_in_memory().unwrap());
+
+    let path = std::env::temp_dir().join("imgbb_ledger_test.db");
+    let _ = std::fs::remove_file(&path);
+
+    SqliteLedger::open(&path)
+        .unwrap()
+        .record(&entry("a", "cat.png", 1_000))
+        .unwrap();
+
+    let reopened = SqliteLedger::open(&path).unwrap();
+    assert_eq!(ids(reopened.entries().unwrap()), ["a"]);
+
+    drop(reopened);
+    std::fs::remove_file(&path).unwrap();
+}
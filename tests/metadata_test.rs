@@ -0,0 +1,355 @@
+use imgbb::metadata::{strip_metadata, strip_metadata_with, MetadataKind, RemovedBlock, StripOptions};
+use imgbb::ImageFormat;
+
+// Tests for removing metadata blocks from images
+
+/// JPEG segment with the given marker and payload
+fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+    let mut segment = vec![0xFF, marker];
+    segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(payload);
+    segment
+}
+
+/// PNG chunk with the given type and payload, with a dummy CRC
+fn chunk(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut chunk = (payload.len() as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(payload);
+    chunk.extend_from_slice(&[0; 4]);
+    chunk
+}
+
+/// WebP chunk with the given FourCC and payload, padded to an even size
+fn riff_chunk(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut chunk = kind.to_vec();
+    chunk.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+fn webp(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body = chunks.concat();
+    let mut webp = b"RIFF".to_vec();
+    webp.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    webp.extend_from_slice(b"WEBP");
+    webp.extend_from_slice(&body);
+    webp
+}
+
+fn kinds(blocks: &[RemovedBlock]) -> Vec<MetadataKind> {
+    blocks.iter().map(|block| block.kind).collect()
+}
+
+fn remove_icc() -> StripOptions {
+    StripOptions::new().remove_icc(true)
+}
+
+/// Little endian TIFF data with a GPS IFD pointer and an orientation tag
+fn tiff_with_orientation(orientation: u16) -> Vec<u8> {
+    let mut tiff = b"II*\0\x08\0\0\0\x02\0".to_vec();
+    // GPS IFD pointer, LONG
+    tiff.extend_from_slice(&[0x25, 0x88, 4, 0, 1, 0, 0, 0, 0x26, 0, 0, 0]);
+    // Orientation, SHORT
+    tiff.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0]);
+    tiff.extend_from_slice(&orientation.to_le_bytes());
+    tiff.extend_from_slice(&[0; 2]);
+    tiff.extend_from_slice(&[0; 4]);
+    tiff.extend_from_slice(b"GPS data");
+    tiff
+}
+
+/// Big endian TIFF data holding only an orientation tag
+fn orientation_tiff(orientation: u8) -> Vec<u8> {
+    [
+        &b"MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0"[..],
+        &[orientation],
+        &[0; 6],
+    ]
+    .concat()
+}
+
+/// GIF extension block with the given label and sub-blocks
+fn extension(label: u8, blocks: &[&[u8]]) -> Vec<u8> {
+    let mut extension = vec![0x21, label];
+    for block in blocks {
+        extension.push(block.len() as u8);
+        extension.extend_from_slice(block);
+    }
+    extension.push(0);
+    extension
+}
+
+#[test]
+fn test_strips_jpeg_metadata_segments() {
+    let jfif = segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+    let exif = segment(0xE1, b"Exif\0\0MM\0*GPS");
+    let xmp = segment(0xE1, b"http://ns.adobe.com/xap/1.0/\0<x:xmpmeta/>");
+    let icc = segment(0xE2, b"ICC_PROFILE\0\x01\x01profile");
+    let iptc = segment(0xED, b"Photoshop 3.0\08BIM");
+    let comment = segment(0xFE, b"taken at HQ");
+    let adobe = segment(0xEE, b"Adobe\0\x64\0\0\0\0\x01");
+    let dqt = segment(0xDB, &[0; 65]);
+    // The scan is copied as is, even if it contains bytes that look like markers
+    let scan = [
+        segment(0xDA, &[1, 1, 0, 0, 0x3F, 0]),
+        vec![0x12, 0xFF, 0x00, 0xFF, 0xD0, 0x34, 0xFF, 0xD9],
+    ]
+    .concat();
+
+    let jpeg = [
+        vec![0xFF, 0xD8],
+        jfif.clone(),
+        exif.clone(),
+        xmp.clone(),
+        icc.clone(),
+        iptc.clone(),
+        comment.clone(),
+        adobe.clone(),
+        dqt.clone(),
+        scan.clone(),
+    ]
+    .concat();
+
+    let (stripped, report) = strip_metadata_with(&jpeg, remove_icc()).unwrap();
+
+    assert_eq!(stripped, [vec![0xFF, 0xD8], jfif.clone(), adobe.clone(), dqt.clone(), scan.clone()].concat());
+    assert_eq!(report.format, ImageFormat::Jpeg);
+    assert_eq!(
+        kinds(&report.removed),
+        [
+            MetadataKind::Exif,
+            MetadataKind::Xmp,
+            MetadataKind::Icc,
+            MetadataKind::Iptc,
+            MetadataKind::Comment,
+        ]
+    );
+    assert_eq!(report.removed[0].size, exif.len());
+    assert_eq!(report.bytes_removed(), jpeg.len() - stripped.len());
+    assert_eq!(report.orientation, None);
+
+    // ICC profiles are kept by default
+    let (stripped, report) = strip_metadata(&jpeg).unwrap();
+    assert_eq!(stripped, [vec![0xFF, 0xD8], jfif, icc, adobe, dqt, scan].concat());
+    assert!(!report.contains(MetadataKind::Icc));
+}
+
+#[test]
+fn test_keeps_exif_orientation() {
+    let dqt = segment(0xDB, &[0; 65]);
+    let exif = segment(0xE1, &[&b"Exif\0\0"[..], &tiff_with_orientation(6)].concat());
+    let jpeg = [vec![0xFF, 0xD8], exif.clone(), dqt.clone(), vec![0xFF, 0xD9]].concat();
+
+    let (stripped, report) = strip_metadata(&jpeg).unwrap();
+
+    let kept = segment(0xE1, &[&b"Exif\0\0"[..], &orientation_tiff(6)].concat());
+    assert_eq!(stripped, [vec![0xFF, 0xD8], kept.clone(), dqt, vec![0xFF, 0xD9]].concat());
+    assert_eq!(report.orientation, Some(6));
+    assert_eq!(kinds(&report.removed), [MetadataKind::Exif]);
+    assert_eq!(report.removed[0].size, exif.len() - kept.len());
+    assert_eq!(report.bytes_removed(), jpeg.len() - stripped.len());
+
+    // The default orientation is not kept
+    let exif = segment(0xE1, &[&b"Exif\0\0"[..], &tiff_with_orientation(1)].concat());
+    let jpeg = [vec![0xFF, 0xD8], exif, vec![0xFF, 0xD9]].concat();
+    let (stripped, report) = strip_metadata(&jpeg).unwrap();
+    assert_eq!(stripped, [0xFF, 0xD8, 0xFF, 0xD9]);
+    assert_eq!(report.orientation, None);
+
+    // PNG eXIf chunks are replaced with a valid CRC
+    let signature = b"\x89PNG\r\n\x1A\n".to_vec();
+    let iend = chunk(b"IEND", b"");
+    let png = [signature.clone(), chunk(b"eXIf", &tiff_with_orientation(8)), iend.clone()].concat();
+    let (stripped, report) = strip_metadata(&png).unwrap();
+    assert_eq!(report.orientation, Some(8));
+    let kept = &stripped[signature.len()..stripped.len() - iend.len()];
+    assert_eq!(&kept[4..8], b"eXIf");
+    assert_eq!(&kept[8..kept.len() - 4], &orientation_tiff(8)[..]);
+    assert_eq!(&kept[kept.len() - 4..], &[0x86, 0x58, 0x30, 0x34]);
+}
+
+#[test]
+fn test_keeps_orientation_of_minimal_exif_blocks() {
+    // One IFD entry and no next IFD offset, smaller than the rebuilt block
+    let tiff = &orientation_tiff(6)[..22];
+
+    let jpeg = [
+        vec![0xFF, 0xD8],
+        segment(0xE1, &[&b"Exif\0\0"[..], tiff].concat()),
+        vec![0xFF, 0xD9],
+    ]
+    .concat();
+    let (stripped, report) = strip_metadata(&jpeg).unwrap();
+    let kept = segment(0xE1, &[&b"Exif\0\0"[..], &orientation_tiff(6)].concat());
+    assert_eq!(stripped, [vec![0xFF, 0xD8], kept, vec![0xFF, 0xD9]].concat());
+    assert_eq!(report.orientation, Some(6));
+    assert_eq!(report.bytes_removed(), 0);
+
+    let signature = b"\x89PNG\r\n\x1A\n".to_vec();
+    let iend = chunk(b"IEND", b"");
+    let png = [signature.clone(), chunk(b"eXIf", tiff), iend.clone()].concat();
+    let (stripped, report) = strip_metadata(&png).unwrap();
+    let kept = &stripped[signature.len()..stripped.len() - iend.len()];
+    assert_eq!(&kept[..kept.len() - 4], &chunk(b"eXIf", &orientation_tiff(6))[..kept.len() - 4]);
+    assert_eq!(report.orientation, Some(6));
+    assert_eq!(report.bytes_removed(), 0);
+
+    let vp8x = riff_chunk(b"VP8X", &[0x08, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let vp8 = riff_chunk(b"VP8 ", b"frame");
+    let webp_image = webp(&[vp8x.clone(), vp8.clone(), riff_chunk(b"EXIF", tiff)]);
+    let (stripped, report) = strip_metadata(&webp_image).unwrap();
+    assert_eq!(stripped, webp(&[vp8x, vp8, riff_chunk(b"EXIF", &orientation_tiff(6))]));
+    assert_eq!(report.orientation, Some(6));
+    assert_eq!(report.bytes_removed(), 0);
+}
+
+#[test]
+fn test_strips_png_metadata_chunks() {
+    let signature = b"\x89PNG\r\n\x1A\n".to_vec();
+    let ihdr = chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+    let idat = chunk(b"IDAT", b"pixels");
+    let iend = chunk(b"IEND", b"");
+
+    let png = [
+        signature.clone(),
+        ihdr.clone(),
+        chunk(b"iCCP", b"sRGB\0\0profile"),
+        chunk(b"tEXt", b"Comment\0taken at HQ"),
+        chunk(b"iTXt", b"XML:com.adobe.xmp\0\0\0\0\0<x:xmpmeta/>"),
+        chunk(b"zTXt", b"Raw profile type iptc\0\0data"),
+        chunk(b"eXIf", b"MM\0*GPS"),
+        idat.clone(),
+        iend.clone(),
+    ]
+    .concat();
+
+    let (stripped, report) = strip_metadata_with(&png, remove_icc()).unwrap();
+
+    assert_eq!(stripped, [signature, ihdr, idat, iend].concat());
+    assert_eq!(report.format, ImageFormat::Png);
+    assert_eq!(
+        kinds(&report.removed),
+        [
+            MetadataKind::Icc,
+            MetadataKind::Comment,
+            MetadataKind::Xmp,
+            MetadataKind::Iptc,
+            MetadataKind::Exif,
+        ]
+    );
+}
+
+#[test]
+fn test_strips_webp_metadata_chunks_and_flags() {
+    let vp8x = |flags: u8| riff_chunk(b"VP8X", &[flags, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let vp8 = riff_chunk(b"VP8 ", b"frame");
+
+    // Alpha flag plus the ICC, EXIF and XMP flags
+    let webp_with_metadata = webp(&[
+        vp8x(0x10 | 0x20 | 0x08 | 0x04),
+        riff_chunk(b"ICCP", b"profile"),
+        vp8.clone(),
+        riff_chunk(b"EXIF", b"MM\0*GPS"),
+        riff_chunk(b"XMP ", b"<x:xmpmeta/>"),
+    ]);
+
+    let (stripped, report) = strip_metadata_with(&webp_with_metadata, remove_icc()).unwrap();
+
+    assert_eq!(stripped, webp(&[vp8x(0x10), vp8.clone()]));
+    assert_eq!(report.format, ImageFormat::Webp);
+    assert_eq!(
+        kinds(&report.removed),
+        [MetadataKind::Icc, MetadataKind::Exif, MetadataKind::Xmp]
+    );
+    // Padding bytes are counted with their chunk
+    assert_eq!(report.removed[1].size, 16);
+
+    // Flags of the kept ICC profile and orientation stay set
+    let webp_with_orientation = webp(&[
+        vp8x(0x10 | 0x20 | 0x08 | 0x04),
+        riff_chunk(b"ICCP", b"profile"),
+        vp8.clone(),
+        riff_chunk(b"EXIF", &tiff_with_orientation(3)),
+        riff_chunk(b"XMP ", b"<x:xmpmeta/>"),
+    ]);
+
+    let (stripped, report) = strip_metadata(&webp_with_orientation).unwrap();
+
+    assert_eq!(
+        stripped,
+        webp(&[
+            vp8x(0x10 | 0x20 | 0x08),
+            riff_chunk(b"ICCP", b"profile"),
+            vp8,
+            riff_chunk(b"EXIF", &orientation_tiff(3)),
+        ])
+    );
+    assert_eq!(report.orientation, Some(3));
+    assert_eq!(kinds(&report.removed), [MetadataKind::Exif, MetadataKind::Xmp]);
+}
+
+#[test]
+fn test_strips_gif_comments_and_metadata_extensions() {
+    // 2 color global color table
+    let header = [&b"GIF89a\x01\0\x01\0\x80\0\0"[..], &[0; 6]].concat();
+    let netscape = extension(0xFF, &[b"NETSCAPE2.0", b"\x01\0\0"]);
+    let graphic_control = extension(0xF9, &[b"\0\0\0\0"]);
+    let image = [&b"\x2C\0\0\0\0\x01\0\x01\0\0\x02"[..], b"\x02\x44\x01\0"].concat();
+    let comment = extension(0xFE, &[b"taken at HQ"]);
+    let xmp = extension(0xFF, &[b"XMP DataXMP", b"<x:xmpmeta/>"]);
+    let icc = extension(0xFF, &[b"ICCRGBG1012", b"profile"]);
+
+    let gif = [
+        header.clone(),
+        netscape.clone(),
+        comment,
+        xmp,
+        icc.clone(),
+        graphic_control.clone(),
+        image.clone(),
+        vec![0x3B],
+    ]
+    .concat();
+
+    let (stripped, report) = strip_metadata(&gif).unwrap();
+
+    assert_eq!(
+        stripped,
+        [header.clone(), netscape.clone(), icc, graphic_control.clone(), image.clone(), vec![0x3B]].concat()
+    );
+    assert_eq!(report.format, ImageFormat::Gif);
+    assert_eq!(kinds(&report.removed), [MetadataKind::Comment, MetadataKind::Xmp]);
+    assert_eq!(report.bytes_removed(), gif.len() - stripped.len());
+
+    let (stripped, report) = strip_metadata_with(&gif, remove_icc()).unwrap();
+    assert_eq!(stripped, [header, netscape, graphic_control, image, vec![0x3B]].concat());
+    assert!(report.contains(MetadataKind::Icc));
+}
+
+#[test]
+fn test_images_without_metadata_are_unchanged() {
+    let jpeg = [vec![0xFF, 0xD8], segment(0xDB, &[0; 65]), vec![0xFF, 0xD9]].concat();
+    let gif = b"GIF89a\x01\0\x01\0\0\0\0;";
+
+    for data in [&jpeg[..], &gif[..]] {
+        let (stripped, report) = strip_metadata(data).unwrap();
+        assert_eq!(stripped, data);
+        assert!(report.removed.is_empty());
+    }
+}
+
+#[test]
+fn test_strip_rejects_malformed_and_unsupported_images() {
+    let truncated = [vec![0xFF, 0xD8], segment(0xE1, b"Exif\0\0MM\0*")[..8].to_vec()].concat();
+    let heic = b"\0\0\0\x18ftypheic\0\0\0\0mif1heic";
+    let truncated_gif = [&b"GIF89a\x01\0\x01\0\0\0\0"[..], &extension(0xFE, &[b"comment"])[..5]].concat();
+
+    for data in [&truncated[..], &heic[..], &truncated_gif[..], b"not an image"] {
+        let result = strip_metadata(data);
+        assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })), "{:?}", result);
+    }
+}
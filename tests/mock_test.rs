@@ -0,0 +1,2373 @@
+use base64::Engine;
+use bytes::Bytes;
+use imgbb::{DedupeCache, ImageFormat, ImgBB, Progress, RateLimiter, RetryPolicy, Transport, UploadLedger, UploadSpec};
+use std::time::Duration;
+use mockito::Matcher;
+
+// Tests that run against a local mock server instead of the real ImgBB API
+
+const SUCCESS_BODY: &str = r#"{
+    "data": {
+        "id": "2ndCYJK",
+        "title": "c1f64245afb2",
+        "url_viewer": "https://ibb.co/2ndCYJK",
+        "url": "https://i.ibb.co/w04Prt6/c1f64245afb2.gif",
+        "display_url": "https://i.ibb.co/98W13PY/c1f64245afb2.gif",
+        "width": 1,
+        "height": 1,
+        "size": 42,
+        "time": 1552042565,
+        "expiration": 0,
+        "image": {
+            "filename": "c1f64245afb2.gif",
+            "name": "c1f64245afb2",
+            "mime": "image/gif",
+            "extension": "gif",
+            "url": "https://i.ibb.co/w04Prt6/c1f64245afb2.gif"
+        },
+        "delete_url": "https://ibb.co/2ndCYJK/670a7e48ddcb85ac340c717a41047e5c"
+    },
+    "success": true,
+    "status": 200
+}"#;
+
+#[tokio::test]
+async fn test_builder_base_url_used_for_upload_builder() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::UrlEncoded("key".into(), "test_key".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let response = imgbb
+        .upload_builder()
+        .bytes(b"image")
+        .name("test")
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(response.id, "2ndCYJK");
+}
+
+#[tokio::test]
+async fn test_builder_base_url_used_for_uploader() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("key".into(), "test_key".into()),
+            Matcher::UrlEncoded("expiration".into(), "600".into()),
+        ]))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    // A trailing slash on the base URL must not produce a double slash
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(format!("{}/", server.url()))
+        .build()
+        .unwrap();
+
+    let response = imgbb
+        .upload_bytes_with_expiration(b"image", 600)
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(response.delete_url, "https://ibb.co/2ndCYJK/670a7e48ddcb85ac340c717a41047e5c");
+}
+
+#[tokio::test]
+async fn test_uploader_base_url_setter() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let client = reqwest::Client::new();
+    let mut uploader = imgbb::uploader::Uploader::new("test_key", &client);
+    uploader.data = Some("aW1hZ2U=".to_string());
+    uploader.base_url(server.url());
+
+    uploader.upload().await.unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_upload_url_sends_url_as_is() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::UrlEncoded(
+            "image".into(),
+            "https://example.com/cat.png?size=large".into(),
+        ))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_url("https://example.com/cat.png?size=large")
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[test]
+fn test_upload_builder_url_rejects_non_http() {
+    let imgbb = ImgBB::new("test_key");
+
+    let result = imgbb.upload_builder().url("ftp://example.com/cat.png");
+    assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })));
+
+    let result = imgbb.upload_builder().url("not a url");
+    assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })));
+
+    assert!(imgbb.upload_builder().url("http://example.com/cat.png").is_ok());
+}
+
+#[tokio::test]
+async fn test_multipart_transport_sends_raw_file_part() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_header("content-type", Matcher::Regex("^multipart/form-data".into()))
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#"name="image"; filename="shot.png""#.into()),
+            Matcher::Regex("Content-Type: image/png".into()),
+            Matcher::Regex("raw-image-bytes".into()),
+            Matcher::Regex(r#"name="title""#.into()),
+        ]))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_builder()
+        .bytes(b"raw-image-bytes")
+        .name("shot.png")
+        .title("Screenshot")
+        .transport(Transport::Multipart)
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_auto_transport_picks_multipart_for_large_input() {
+    let mut server = mockito::Server::new_async().await;
+    let multipart = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_header("content-type", Matcher::Regex("^multipart/form-data".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(1)
+        .create_async()
+        .await;
+    let form = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_header("content-type", "application/x-www-form-urlencoded")
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let large = vec![0u8; imgbb::transport::MULTIPART_THRESHOLD];
+    imgbb.upload_bytes(&large).await.unwrap();
+    imgbb.upload_bytes(b"small").await.unwrap();
+
+    multipart.assert_async().await;
+    form.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_uploader_multipart_decodes_base64() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_header("content-type", Matcher::Regex("^multipart/form-data".into()))
+        .match_body(Matcher::Regex("\r\n\r\nimage\r\n".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let client = reqwest::Client::new();
+    let mut uploader = imgbb::uploader::Uploader::new("test_key", &client);
+    uploader.data = Some("aW1hZ2U=".to_string());
+    uploader.base_url(server.url());
+    uploader.transport(Transport::Multipart);
+
+    uploader.upload().await.unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_reader_streams_multipart_body() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_header("content-type", Matcher::Regex("^multipart/form-data".into()))
+        .match_body(Matcher::Regex("streamed-image-bytes".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_builder()
+        .reader(&b"streamed-image-bytes"[..])
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_stream_with_form_transport_encodes_on_the_fly() {
+    let chunks = vec![
+        Bytes::from_static(b"\xfb\xff"),
+        Bytes::from_static(b"\xfe?>"),
+        Bytes::from_static(b"a"),
+        Bytes::from_static(b"bcdefg"),
+    ];
+    let expected = base64::engine::general_purpose::STANDARD.encode(b"\xfb\xff\xfe?>abcdefg");
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_header("content-type", "application/x-www-form-urlencoded")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("image".into(), expected),
+            Matcher::UrlEncoded("title".into(), "Streamed & encoded".into()),
+        ]))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_builder()
+        .stream(futures_util::stream::iter(chunks))
+        .title("Streamed & encoded")
+        .transport(Transport::Form)
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_file_streamed_labels_part_with_file_name() {
+    let path = std::env::temp_dir().join("imgbb_mock_streamed.png");
+    std::fs::write(&path, b"png-file-bytes").unwrap();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#"filename="imgbb_mock_streamed.png""#.into()),
+            Matcher::Regex("Content-Type: image/png".into()),
+            Matcher::Regex("png-file-bytes".into()),
+        ]))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_builder()
+        .file_streamed(&path)
+        .await
+        .unwrap()
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_stream_can_only_be_uploaded_once() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let builder = imgbb.upload_builder().reader(&b"bytes"[..]);
+    builder.clone().upload().await.unwrap();
+
+    let result = builder.upload().await;
+    assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })));
+}
+
+fn fast_retry() -> RetryPolicy {
+    RetryPolicy::new()
+        .max_attempts(3)
+        .base_delay(Duration::from_millis(1))
+        .jitter(false)
+}
+
+#[tokio::test]
+async fn test_retry_recovers_from_server_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let failing = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(503)
+        .with_body("Service Unavailable")
+        .expect(2)
+        .create_async()
+        .await;
+    let success = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    imgbb.upload_bytes(b"image").await.unwrap();
+
+    failing.assert_async().await;
+    success.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_retry_gives_up_after_max_attempts() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(500)
+        .with_body("Internal Server Error")
+        .expect(3)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let result = imgbb.upload_builder().bytes(b"image").upload().await;
+
+    assert!(matches!(result, Err(imgbb::Error::ApiError { status: Some(500), .. })));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_retry_skips_non_retryable_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(400)
+        .with_body(r#"{"error":{"message":"Invalid API v1 key.","code":100}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let result = imgbb.upload_builder().bytes(b"image").upload().await;
+
+    assert!(matches!(result, Err(imgbb::Error::InvalidApiKey { .. })));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_retry_skips_malformed_responses() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // A server that answers every request with something that is not HTTP
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut buffer = [0; 1024];
+            let _ = socket.read(&mut buffer).await;
+            let _ = socket.write_all(b"not http\r\n\r\n").await;
+        }
+    });
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(url)
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let error = imgbb.upload_bytes(b"image").await.unwrap_err();
+
+    assert!(matches!(error, imgbb::Error::ReqwestError(_)), "{:?}", error);
+    assert!(!error.is_retryable());
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_retry_honors_retry_after() {
+    let mut server = mockito::Server::new_async().await;
+    let limited = server
+        .mock("DELETE", "/abc/def")
+        .match_query(Matcher::Any)
+        .with_status(429)
+        .with_header("retry-after", "1")
+        .with_body(r#"{"error":{"message":"Rate limit","code":429}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let success = server
+        .mock("DELETE", "/abc/def")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    imgbb.delete(format!("{}/abc/def", server.url())).await.unwrap();
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    limited.assert_async().await;
+    success.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_retry_gives_up_when_retry_after_exceeds_max_delay() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(429)
+        .with_header("retry-after", "3600")
+        .with_body(r#"{"error":{"message":"Rate limit","code":429}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .retry(fast_retry().max_delay(Duration::from_secs(5)))
+        .build()
+        .unwrap();
+
+    let result = imgbb.upload_builder().bytes(b"image").upload().await;
+
+    assert!(matches!(result, Err(imgbb::Error::RateLimitExceeded { .. })));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_retry_custom_predicate() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(503)
+        .with_body("Service Unavailable")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .retry(fast_retry().retry_if(|error| matches!(error, imgbb::Error::RateLimitExceeded { .. })))
+        .build()
+        .unwrap();
+
+    assert!(imgbb.upload_bytes(b"image").await.is_err());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_rate_limiter_spaces_requests() {
+    let mut server = mockito::Server::new_async().await;
+    let upload = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+    let delete = server
+        .mock("DELETE", "/abc/def")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    // 10 requests per second without bursts: one request every 100 ms
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .rate_limiter(RateLimiter::per_second(10).burst(1))
+        .build()
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    imgbb.upload_bytes(b"image").await.unwrap();
+    imgbb.upload_builder().bytes(b"image").upload().await.unwrap();
+    imgbb.delete(format!("{}/abc/def", server.url())).await.unwrap();
+
+    assert!(started.elapsed() >= Duration::from_millis(190));
+    upload.assert_async().await;
+    delete.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_rate_limiter_allows_burst() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .rate_limiter(RateLimiter::per_minute(1).burst(3))
+        .build()
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    for _ in 0..3 {
+        imgbb.upload_bytes(b"image").await.unwrap();
+    }
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_upload_batch_returns_results_in_input_order() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::Regex("title=Batch".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let imgbb = std::sync::Arc::new(
+        ImgBB::builder("test_key")
+            .base_url(server.url())
+            .build()
+            .unwrap(),
+    );
+
+    let specs = vec![
+        UploadSpec::bytes(b"first".to_vec()).title("Batch"),
+        UploadSpec::url("ftp://example.com/invalid.png").title("Batch"),
+        UploadSpec::from(imgbb.upload_builder().bytes(b"second")).title("Batch"),
+        UploadSpec::base64("dGhpcmQ=").title("Batch").expiration(600),
+    ];
+
+    // Batches can run on spawned tasks
+    let client = imgbb.clone();
+    let results = tokio::spawn(async move { client.upload_batch(specs, 2).await })
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(imgbb::Error::InvalidParameters { .. })));
+    assert!(results[2].is_ok());
+    assert!(results[3].is_ok());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_upload_batch_stream_yields_every_item() {
+    use futures_util::StreamExt;
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let specs = (0..5).map(|i| UploadSpec::bytes(vec![i; 8]));
+    let mut indices: Vec<usize> = imgbb
+        .upload_batch_stream(specs, 3)
+        .map(|(index, result)| {
+            assert!(result.is_ok());
+            index
+        })
+        .collect()
+        .await;
+
+    indices.sort_unstable();
+    assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+}
+
+#[tokio::test]
+async fn test_progress_reports_form_upload() {
+    let data = vec![0xfbu8; 200 * 1024];
+    let expected = base64::engine::general_purpose::STANDARD.encode(&data);
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("image".into(), expected),
+            Matcher::UrlEncoded("title".into(), "Progress & form".into()),
+        ]))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let updates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = updates.clone();
+
+    imgbb
+        .upload_builder()
+        .bytes(&data)
+        .title("Progress & form")
+        .transport(Transport::Form)
+        .on_progress(move |progress| recorded.lock().unwrap().push(progress))
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+
+    let updates = updates.lock().unwrap();
+    let last = *updates.last().unwrap();
+    assert!(updates.len() > 2);
+    assert_eq!(last.bytes_encoded, data.len() as u64);
+    assert_eq!(Some(last.bytes_sent), last.total_bytes);
+    assert_eq!(last.fraction(), Some(1.0));
+    assert!(updates.windows(2).all(|w| w[0].bytes_sent <= w[1].bytes_sent));
+}
+
+#[tokio::test]
+async fn test_progress_watch_reports_streamed_multipart_upload() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::Regex("first-second".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let chunks = vec![Bytes::from_static(b"first-"), Bytes::from_static(b"second")];
+    let (sender, receiver) = tokio::sync::watch::channel(Progress::default());
+
+    imgbb
+        .upload_builder()
+        .stream(futures_util::stream::iter(chunks))
+        .progress_watch(sender)
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(
+        *receiver.borrow(),
+        Progress {
+            bytes_encoded: 12,
+            bytes_sent: 12,
+            total_bytes: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_upload_batch_with_progress_aggregates_items() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let specs = vec![
+        UploadSpec::bytes(vec![1; 300]),
+        UploadSpec::url("ftp://example.com/invalid.png"),
+        UploadSpec::from(
+            imgbb
+                .upload_builder()
+                .bytes(vec![2; 100])
+                .transport(Transport::Multipart),
+        ),
+    ];
+
+    let updates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = updates.clone();
+    let results = imgbb
+        .upload_batch_with_progress(specs, 2, move |progress| {
+            recorded.lock().unwrap().push(progress)
+        })
+        .await;
+
+    assert_eq!(results.len(), 3);
+
+    let last = *updates.lock().unwrap().last().unwrap();
+    assert_eq!(last.items_total, 3);
+    assert_eq!(last.items_completed, 3);
+    assert_eq!(last.items_failed, 1);
+    assert_eq!(last.bytes_encoded, 400);
+    // 300 bytes encode to 400 base64 characters, multipart sends raw bytes
+    assert_eq!(last.bytes_sent, 500);
+    assert_eq!(last.total_bytes, 500);
+}
+
+/// Run the same failing response through every async entry point
+async fn errors_from_every_path(status: usize, body: &str) -> Vec<imgbb::Error> {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(status)
+        .with_body(body)
+        .create_async()
+        .await;
+    server
+        .mock("DELETE", "/abc/123")
+        .match_query(Matcher::Any)
+        .with_status(status)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    vec![
+        imgbb.read_bytes(b"image").upload().await.unwrap_err(),
+        imgbb.upload_builder().bytes(b"image").upload().await.unwrap_err(),
+        imgbb
+            .delete(format!("{}/abc/123", server.url()))
+            .await
+            .unwrap_err(),
+    ]
+}
+
+#[tokio::test]
+async fn test_error_codes_map_consistently_across_paths() {
+    let error_body = |code: u16| {
+        format!(
+            r#"{{"status_code":400,"error":{{"message":"Error {}","code":{}}},"status_txt":"Bad Request"}}"#,
+            code, code
+        )
+    };
+
+    for error in errors_from_every_path(400, &error_body(100)).await {
+        assert!(matches!(error, imgbb::Error::InvalidApiKey { .. }), "{:?}", error);
+    }
+    for error in errors_from_every_path(400, &error_body(120)).await {
+        assert!(matches!(error, imgbb::Error::InvalidBase64Data { .. }), "{:?}", error);
+    }
+    for error in errors_from_every_path(400, &error_body(313)).await {
+        assert!(matches!(error, imgbb::Error::ImageTooLarge { .. }), "{:?}", error);
+    }
+    for error in errors_from_every_path(400, &error_body(401)).await {
+        assert!(matches!(error, imgbb::Error::UnsupportedFormat { .. }), "{:?}", error);
+    }
+    for error in errors_from_every_path(400, &error_body(130)).await {
+        assert!(matches!(error, imgbb::Error::InvalidParameters { .. }), "{:?}", error);
+    }
+    for error in errors_from_every_path(400, &error_body(999)).await {
+        assert!(
+            matches!(error, imgbb::Error::ApiError { code: Some(imgbb::ApiErrorCode::Unknown(999)), status: Some(400), .. }),
+            "{:?}",
+            error
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_unsuccessful_and_non_json_responses_map_consistently() {
+    for error in errors_from_every_path(200, r#"{"success":false,"status":200}"#).await {
+        assert!(matches!(error, imgbb::Error::ApiError { code: None, .. }), "{:?}", error);
+    }
+    for error in errors_from_every_path(429, "<html>Too Many Requests</html>").await {
+        assert!(matches!(error, imgbb::Error::RateLimitExceeded { .. }), "{:?}", error);
+    }
+    for error in errors_from_every_path(502, "<html>Bad Gateway</html>").await {
+        assert!(
+            matches!(error, imgbb::Error::ApiError { status: Some(502), code: None, .. }),
+            "{:?}",
+            error
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_errors_carry_response_context() {
+    let long_body = format!("<html>{}</html>", "x".repeat(5000));
+
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(429)
+        .with_header("retry-after", "120")
+        .with_header("x-ratelimit-remaining", "0")
+        .with_header("x-unrelated", "dropped")
+        .with_body(&long_body)
+        .create_async()
+        .await;
+    server
+        .mock("DELETE", "/abc/123")
+        .match_query(Matcher::Any)
+        .with_status(400)
+        .with_body(r#"{"status_code":400,"error":{"message":"Invalid API v1 key.","code":100}}"#)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let error = imgbb.upload_bytes(b"image").await.unwrap_err();
+    assert!(matches!(error, imgbb::Error::RateLimitExceeded { .. }));
+    assert_eq!(error.status(), Some(429));
+
+    let context = error.context().unwrap();
+    assert_eq!(context.kind, imgbb::RequestKind::Upload);
+    assert_eq!(context.header("Retry-After"), Some("120"));
+    assert_eq!(context.header("x-ratelimit-remaining"), Some("0"));
+    assert_eq!(context.header("x-unrelated"), None);
+    assert!(context.truncated);
+    assert_eq!(context.body.len(), imgbb::error::MAX_CONTEXT_BODY_LEN);
+    assert!(long_body.starts_with(&context.body));
+
+    let error = imgbb
+        .delete(format!("{}/abc/123", server.url()))
+        .await
+        .unwrap_err();
+    let context = error.context().unwrap();
+    assert!(matches!(error, imgbb::Error::InvalidApiKey { .. }));
+    assert_eq!(context.kind, imgbb::RequestKind::Delete);
+    assert_eq!(context.status, 400);
+    assert!(!context.truncated);
+    assert!(context.body.contains("Invalid API v1 key."));
+}
+
+#[tokio::test]
+async fn test_timeouts_and_connection_failures_are_classified() {
+    // A server that accepts connections but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let silent_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            connections.push(socket);
+        }
+    });
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(silent_url)
+        .timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+
+    let error = imgbb.upload_bytes(b"image").await.unwrap_err();
+    assert!(matches!(error, imgbb::Error::Timeout), "{:?}", error);
+    assert!(error.is_retryable());
+    assert!(!error.is_client_error());
+
+    // Nothing listens on a port that was just released
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed_url = format!("http://{}", closed.local_addr().unwrap());
+    drop(closed);
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(closed_url)
+        .build()
+        .unwrap();
+
+    let error = imgbb.upload_bytes(b"image").await.unwrap_err();
+    assert!(matches!(error, imgbb::Error::Connection(_)), "{:?}", error);
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn test_error_classification_helpers() {
+    let error_body = |code: u16| {
+        format!(
+            r#"{{"status_code":400,"error":{{"message":"Error {}","code":{}}}}}"#,
+            code, code
+        )
+    };
+
+    for error in errors_from_every_path(400, &error_body(313)).await {
+        assert_eq!(error.api_code(), Some(imgbb::ApiErrorCode::ImageTooLarge));
+        assert!(error.is_client_error());
+        assert!(!error.is_retryable());
+    }
+    for error in errors_from_every_path(400, &error_body(999)).await {
+        assert_eq!(error.api_code(), Some(imgbb::ApiErrorCode::Unknown(999)));
+        assert!(error.is_client_error());
+    }
+    for error in errors_from_every_path(429, "Too Many Requests").await {
+        assert_eq!(error.api_code(), None);
+        assert!(error.is_retryable());
+        assert!(!error.is_client_error());
+    }
+    for error in errors_from_every_path(503, "Service Unavailable").await {
+        assert!(error.is_retryable());
+        assert!(!error.is_client_error());
+    }
+
+    let local = match ImgBB::new("test_key")
+        .upload_builder()
+        .url("ftp://example.com/image.png")
+    {
+        Err(error) => error,
+        Ok(_) => panic!("ftp URLs must be rejected"),
+    };
+    assert!(local.is_client_error());
+    assert_eq!(local.api_code(), None);
+    assert_eq!(local.status(), None);
+}
+
+#[tokio::test]
+async fn test_upload_returns_validated_image() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let image = imgbb.upload_bytes(b"image").await.unwrap();
+    assert_eq!(image.id, "2ndCYJK");
+    assert_eq!(image.url, "https://i.ibb.co/w04Prt6/c1f64245afb2.gif");
+    assert_eq!(image.display_url, "https://i.ibb.co/98W13PY/c1f64245afb2.gif");
+    assert_eq!((image.width, image.height, image.size), (1, 1, 42));
+
+    // The loose response stays available
+    let response = imgbb
+        .upload_builder()
+        .bytes(b"image")
+        .upload_response()
+        .await
+        .unwrap();
+    assert_eq!(response.success, Some(true));
+}
+
+#[tokio::test]
+async fn test_upload_reports_missing_required_fields() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{"data":{"id":"2ndCYJK","url":"https://i.ibb.co/x.gif"},"success":true,"status":200}"#)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let error = imgbb.upload_bytes(b"image").await.unwrap_err();
+    assert!(
+        matches!(&error, imgbb::Error::MissingField(field) if field == "data.display_url"),
+        "{:?}",
+        error
+    );
+
+    let response = imgbb
+        .upload_builder()
+        .bytes(b"image")
+        .upload_response()
+        .await
+        .unwrap();
+    assert_eq!(response.data.unwrap().id.as_deref(), Some("2ndCYJK"));
+}
+
+#[tokio::test]
+async fn test_expiration_accepts_duration() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::UrlEncoded("expiration".into(), "86400".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_builder()
+        .bytes(b"image")
+        .expiration(Duration::from_secs(24 * 60 * 60))
+        .upload()
+        .await
+        .unwrap();
+
+    let specs = vec![UploadSpec::bytes(b"image".to_vec()).expiration(Duration::from_secs(86400))];
+    for result in imgbb.upload_batch(specs, 1).await {
+        result.unwrap();
+    }
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_out_of_range_expiration_is_rejected_before_sending() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    for expiration in [Duration::from_secs(59), Duration::from_secs(15_552_001)] {
+        let result = imgbb
+            .upload_builder()
+            .bytes(b"image")
+            .expiration(expiration)
+            .upload()
+            .await;
+        match result {
+            Err(imgbb::Error::InvalidParameters { message, context: None }) => {
+                assert!(message.contains("between 60 and 15552000 seconds"), "{}", message)
+            }
+            Err(err) => panic!("Unexpected error type: {:?}", err),
+            Ok(_) => panic!("Expected error but got success"),
+        }
+    }
+
+    let error = imgbb.upload_bytes_with_expiration(b"image", 30).await.unwrap_err();
+    assert!(matches!(error, imgbb::Error::InvalidParameters { .. }), "{:?}", error);
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_unsupported_format_is_rejected_before_sending() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let svg = br#"<svg xmlns="http://www.w3.org/2000/svg"></svg>"#;
+    let svg_base64 = base64::engine::general_purpose::STANDARD.encode(svg);
+
+    let builder = imgbb.upload_builder().bytes(svg);
+    assert_eq!(builder.format(), Some(ImageFormat::Svg));
+
+    let errors = vec![
+        builder.upload().await.err(),
+        imgbb.upload_builder().data(&svg_base64).upload().await.err(),
+        imgbb.upload_bytes(svg).await.err(),
+    ];
+
+    for error in errors {
+        match error {
+            Some(error @ imgbb::Error::UnsupportedFormat { format: Some(ImageFormat::Svg), context: None }) => {
+                assert_eq!(error.to_string(), "Unsupported image format: SVG")
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_multipart_part_labelled_with_detected_format() {
+    let png = b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR";
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#"name="image"; filename="image""#.into()),
+            Matcher::Regex("Content-Type: image/png".into()),
+        ]))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let builder = imgbb.upload_builder().bytes(png).transport(Transport::Multipart);
+    assert_eq!(builder.format().map(|format| format.mime_type()), Some("image/png"));
+    builder.upload().await.unwrap();
+
+    // The legacy uploader labels decoded base64 data the same way
+    let mut uploader = imgbb.read_bytes(png);
+    uploader.transport(Transport::Multipart);
+    uploader.upload().await.unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_oversized_images_are_rejected_before_sending() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .max_image_size(8)
+        .build()
+        .unwrap();
+
+    let path = std::env::temp_dir().join("imgbb_mock_oversized.png");
+    std::fs::write(&path, [0u8; 16]).unwrap();
+
+    fn assert_too_large(error: Option<imgbb::Error>, size: u64) {
+        match error {
+            Some(error @ imgbb::Error::ImageTooLarge { size: Some(actual), max_size: Some(8), context: None }) => {
+                assert_eq!(actual, size);
+                assert_eq!(
+                    error.to_string(),
+                    format!("Image too large: {} bytes, the limit is 8 bytes", size)
+                );
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    // Files are checked before they are read, and as soon as they are set
+    // unless they may be shrunk to fit
+    let file = match imgbb.upload_builder().file(&path) {
+        Ok(builder) => builder.upload().await.err(),
+        Err(error) => Some(error),
+    };
+    assert_too_large(file, 16);
+    assert_too_large(imgbb.upload_builder().file_streamed(&path).await.err(), 16);
+    assert_too_large(imgbb.read_file(&path).err(), 16);
+
+    assert_too_large(imgbb.upload_builder().bytes([0u8; 9]).upload().await.err(), 9);
+    assert_too_large(imgbb.upload_builder().data("AAAAAAAAAAAA").upload().await.err(), 9);
+    assert_too_large(imgbb.upload_bytes([0u8; 12]).await.err(), 12);
+    assert_too_large(imgbb.read_bytes([0u8; 9]).upload().await.err(), 9);
+
+    // The size is checked before the image is parsed to strip metadata
+    let stripped = imgbb.upload_builder().bytes([0u8; 9]).strip_metadata(true);
+    assert_too_large(stripped.upload().await.err(), 9);
+
+    // Images at the limit are sent, padding aside
+    imgbb.upload_builder().bytes([0u8; 8]).upload().await.unwrap();
+    imgbb.upload_builder().data("AAAAAAAAAAA=").upload().await.unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+    mock.assert_async().await;
+}
+
+/// Encode a noisy RGB image, which compresses poorly
+#[cfg(feature = "image-processing")]
+fn noisy_image(width: u32, height: u32, format: image::ImageFormat) -> Vec<u8> {
+    let mut state = 0x2545_F491u32;
+    let image = image::RgbImage::from_fn(width, height, |_, _| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        image::Rgb([state as u8, (state >> 8) as u8, (state >> 16) as u8])
+    });
+
+    let mut data = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut data, format).unwrap();
+    data.into_inner()
+}
+
+#[cfg(feature = "image-processing")]
+#[tokio::test]
+async fn test_fit_within_shrinks_large_images() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(4)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .max_image_size(200_000)
+        .build()
+        .unwrap();
+
+    let png = noisy_image(400, 300, image::ImageFormat::Png);
+    assert!(png.len() > 200_000);
+
+    // The file is read although it exceeds the client's limit
+    let path = std::env::temp_dir().join("imgbb_mock_fit.png");
+    std::fs::write(&path, &png).unwrap();
+
+    let upload = imgbb
+        .upload_builder()
+        .fit_within(100_000, 256)
+        .file(&path)
+        .unwrap()
+        .upload_processed()
+        .await
+        .unwrap();
+
+    // The policy may also be set after the file
+    imgbb
+        .upload_builder()
+        .file(&path)
+        .unwrap()
+        .fit_within(100_000, 256)
+        .upload()
+        .await
+        .unwrap();
+
+    // Without a policy, the file is rejected before it is read
+    let result = imgbb.upload_builder().file(&path).unwrap().upload().await;
+    assert!(
+        matches!(result, Err(imgbb::Error::ImageTooLarge { size: Some(size), .. }) if size == png.len() as u64),
+        "{:?}",
+        result
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(upload.image.id, "2ndCYJK");
+    let transformation = upload.transformation.unwrap();
+    assert_eq!(transformation.original_format, Some(ImageFormat::Png));
+    assert_eq!(transformation.original_size, png.len() as u64);
+    assert_eq!(transformation.original_dimensions, (400, 300));
+    assert!(transformation.size <= 100_000);
+    assert!(transformation.dimensions.0 <= 256 && transformation.dimensions.1 <= 192);
+    assert!(transformation.resized());
+    assert_eq!(transformation.quality.is_some(), transformation.format == ImageFormat::Jpeg);
+
+    // upload applies the policy too
+    imgbb
+        .upload_builder()
+        .bytes(&png)
+        .fit_within(100_000, 256)
+        .upload()
+        .await
+        .unwrap();
+
+    // Images that already fit are sent unchanged
+    let small = noisy_image(16, 16, image::ImageFormat::Png);
+    let upload = imgbb
+        .upload_builder()
+        .bytes(&small)
+        .fit_within(100_000, 256)
+        .upload_processed()
+        .await
+        .unwrap();
+    assert!(upload.transformation.is_none());
+
+    mock.assert_async().await;
+}
+
+#[cfg(feature = "image-processing")]
+#[tokio::test]
+async fn test_fit_within_reports_undecodable_images() {
+    let imgbb = ImgBB::new("test_key");
+
+    let result = imgbb
+        .upload_builder()
+        .bytes(b"\x89PNG\r\n\x1A\nbroken")
+        .fit_within(1, 1)
+        .upload_processed()
+        .await;
+    assert!(matches!(result, Err(imgbb::Error::ImageProcessing(_))), "{:?}", result.err());
+}
+
+#[cfg(feature = "image-processing")]
+#[tokio::test]
+async fn test_fit_within_applies_orientation_and_keeps_icc_profile() {
+    use image::ImageEncoder;
+
+    // A 400x200 JPEG with an ICC profile, shown rotated by 90 degrees
+    let mut jpeg = Vec::new();
+    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 90);
+    encoder.set_icc_profile(b"test icc profile".to_vec()).unwrap();
+    let pixels = image::RgbImage::from_fn(400, 200, |x, _| image::Rgb([(x % 256) as u8, 0, 0]));
+    encoder
+        .write_image(&pixels, 400, 200, image::ExtendedColorType::Rgb8)
+        .unwrap();
+
+    let tiff = b"MM\0\x2A\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0";
+    let exif = [&b"\xFF\xE1\0\x22Exif\0\0"[..], tiff].concat();
+    let jpeg = [&jpeg[..2], &exif, &jpeg[2..]].concat();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::Regex("ICC_PROFILE".to_string()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let upload = imgbb
+        .upload_builder()
+        .bytes(&jpeg)
+        .transport(Transport::Multipart)
+        .fit_within(1_000_000, 100)
+        .upload_processed()
+        .await
+        .unwrap();
+
+    let transformation = upload.transformation.unwrap();
+    assert_eq!(transformation.original_dimensions, (200, 400));
+    assert_eq!(transformation.dimensions, (50, 100));
+    assert_eq!(transformation.format, ImageFormat::Jpeg);
+    assert!(!transformation.icc_profile_dropped);
+
+    mock.assert_async().await;
+}
+
+#[cfg(feature = "image-processing")]
+#[tokio::test]
+async fn test_fit_within_rejects_zero_limits() {
+    let imgbb = ImgBB::new("test_key");
+    let png = noisy_image(16, 16, image::ImageFormat::Png);
+
+    for (max_bytes, max_dimension) in [(0, 100), (100_000, 0)] {
+        let result = imgbb
+            .upload_builder()
+            .bytes(&png)
+            .fit_within(max_bytes, max_dimension)
+            .upload()
+            .await;
+        assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })), "{:?}", result);
+    }
+}
+
+#[tokio::test]
+async fn test_strip_metadata_uploads_stripped_image() {
+    let comment = b"\xFF\xFE\0\x0Dtaken at HQ";
+    let jpeg = [&b"\xFF\xD8"[..], comment, b"\xFF\xD9"].concat();
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_body(Matcher::UrlEncoded("image".into(), "/9j/2Q==".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let upload = imgbb
+        .upload_builder()
+        .bytes(&jpeg)
+        .strip_metadata(true)
+        .upload_processed()
+        .await
+        .unwrap();
+
+    let metadata = upload.metadata.unwrap();
+    assert_eq!(metadata.removed.len(), 1);
+    assert!(metadata.contains(imgbb::MetadataKind::Comment));
+    assert_eq!(metadata.bytes_removed(), comment.len());
+
+    imgbb
+        .upload_builder()
+        .bytes(&jpeg)
+        .strip_metadata(true)
+        .upload()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_strip_metadata_refuses_images_it_cannot_strip() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let heic = b"\0\0\0\x18ftypheic\0\0\0\0mif1heic";
+    let result = imgbb
+        .upload_builder()
+        .bytes(heic)
+        .strip_metadata(true)
+        .upload()
+        .await;
+    assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })), "{:?}", result.err());
+
+    let result = imgbb
+        .upload_builder()
+        .url("https://example.com/photo.jpg")
+        .unwrap()
+        .strip_metadata(true)
+        .upload()
+        .await;
+    assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })), "{:?}", result.err());
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_dedupe_cache_skips_identical_uploads() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let cache = std::sync::Arc::new(imgbb::dedupe::MemoryCache::new());
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .dedupe_cache(cache.clone())
+        .build()
+        .unwrap();
+
+    let first = imgbb.upload_builder().bytes(b"image").upload().await.unwrap();
+    assert_eq!(cache.len(), 1);
+
+    // Identical bytes are served from the cache, whichever way they are given
+    let second = imgbb.upload_builder().bytes(b"image").title("Other").upload().await.unwrap();
+    imgbb.upload_builder().data("aW1hZ2U=").upload().await.unwrap();
+    imgbb.upload_bytes(b"image").await.unwrap();
+    assert_eq!(first.id, second.id);
+
+    // Other bytes are uploaded
+    imgbb.upload_builder().bytes(b"other").upload().await.unwrap();
+    assert_eq!(cache.len(), 2);
+
+    mock.assert_async().await;
+}
+
+/// Dedupe cache that can be neither read nor written
+#[derive(Debug)]
+struct BrokenCache;
+
+fn denied() -> imgbb::Error {
+    std::io::Error::from(std::io::ErrorKind::PermissionDenied).into()
+}
+
+impl imgbb::dedupe::DedupeCache for BrokenCache {
+    fn get(&self, _: &imgbb::dedupe::ContentHash) -> Result<Option<imgbb::model::Response>, imgbb::Error> {
+        Err(denied())
+    }
+
+    fn insert(&self, _: imgbb::dedupe::ContentHash, _: &imgbb::model::Response) -> Result<(), imgbb::Error> {
+        Err(denied())
+    }
+
+    fn remove(&self, _: &imgbb::dedupe::ContentHash) -> Result<(), imgbb::Error> {
+        Err(denied())
+    }
+
+    fn evict_expired(&self, _: std::time::SystemTime) -> Result<usize, imgbb::Error> {
+        Err(denied())
+    }
+}
+
+#[tokio::test]
+async fn test_dedupe_cache_failures_do_not_fail_uploads() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .dedupe_cache(BrokenCache)
+        .build()
+        .unwrap();
+
+    let image = imgbb.upload_builder().bytes(b"image").upload().await.unwrap();
+    assert!(!image.delete_url.is_empty());
+    imgbb.upload_bytes(b"image").await.unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_dedupe_cache_reuploads_expired_images() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create_async()
+        .await;
+
+    // Uploaded in 2019 with a one minute lifetime
+    let mut expired: imgbb::model::Response = serde_json::from_str(SUCCESS_BODY).unwrap();
+    expired.data.as_mut().unwrap().expiration = Some(60);
+    expired.data.as_mut().unwrap().id = Some("expired".to_string());
+
+    let cache = std::sync::Arc::new(imgbb::dedupe::MemoryCache::new());
+    cache
+        .insert(imgbb::dedupe::ContentHash::of(b"image"), &expired)
+        .unwrap();
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .dedupe_cache(cache.clone())
+        .build()
+        .unwrap();
+
+    let image = imgbb.upload_builder().bytes(b"image").upload().await.unwrap();
+    assert_eq!(image.id, "2ndCYJK");
+    assert_eq!(cache.len(), 1);
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_ledger_records_uploads() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let path = std::env::temp_dir().join("imgbb_mock_ledger.png");
+    std::fs::write(&path, b"ledger-image").unwrap();
+
+    let ledger = std::sync::Arc::new(imgbb::ledger::JsonLinesLedger::open(
+        std::env::temp_dir().join("imgbb_mock_ledger.jsonl"),
+    ));
+    let _ = std::fs::remove_file(ledger.path());
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .ledger(ledger.clone())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_builder()
+        .file(&path)
+        .unwrap()
+        .title("Ledger")
+        .album("album_id")
+        .upload()
+        .await
+        .unwrap();
+
+    let entry = imgbb.ledger().unwrap().find_by_id("2ndCYJK").unwrap().unwrap();
+    assert_eq!(entry.source_path.as_deref(), Some(path.as_path()));
+    assert_eq!(entry.hash, Some(imgbb::dedupe::ContentHash::of(b"ledger-image")));
+    assert_eq!(entry.title.as_deref(), Some("Ledger"));
+    assert_eq!(entry.album.as_deref(), Some("album_id"));
+    assert_eq!(entry.name.as_deref(), Some("c1f64245afb2"));
+    assert_eq!(entry.time, 1552042565);
+    assert_eq!(entry.expiration, None);
+    assert_eq!(
+        entry.delete_url.as_deref(),
+        Some("https://ibb.co/2ndCYJK/670a7e48ddcb85ac340c717a41047e5c")
+    );
+
+    // The legacy uploader records the file it read as well
+    std::fs::remove_file(ledger.path()).unwrap();
+    imgbb.upload_file(&path).await.unwrap();
+    let entries = ledger.find_by_source(&path).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].title.as_deref(), Some("c1f64245afb2"));
+
+    mock.assert_async().await;
+    std::fs::remove_file(ledger.path()).unwrap();
+}
+
+#[tokio::test]
+async fn test_delete_recorded_removes_deleted_entries() {
+    let mut server = mockito::Server::new_async().await;
+    let delete = server
+        .mock("DELETE", "/abc/def")
+        .match_query(Matcher::UrlEncoded("key".into(), "test_key".into()))
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let ledger = std::sync::Arc::new(imgbb::ledger::JsonLinesLedger::open(
+        std::env::temp_dir().join("imgbb_mock_delete_recorded.jsonl"),
+    ));
+    let _ = std::fs::remove_file(ledger.path());
+
+    let entry = |id: &str, delete_url: Option<String>| imgbb::LedgerEntry {
+        id: id.to_string(),
+        url: None,
+        url_viewer: None,
+        display_url: None,
+        delete_url,
+        hash: Some(imgbb::dedupe::ContentHash::of(id.as_bytes())),
+        source_path: None,
+        name: None,
+        title: None,
+        album: None,
+        time: 1552042565,
+        expiration: None,
+        status: imgbb::ledger::EntryStatus::Active,
+    };
+    ledger.record(&entry("deleted", Some(format!("{}/abc/def", server.url())))).unwrap();
+    ledger.record(&entry("no_url", None)).unwrap();
+
+    // A cache that cannot be written does not keep deleted entries in the ledger
+    let imgbb = ImgBB::builder("test_key")
+        .ledger(ledger.clone())
+        .dedupe_cache(BrokenCache)
+        .build()
+        .unwrap();
+
+    let results = imgbb.delete_recorded(ledger.entries().unwrap()).await;
+    assert!(results[0].is_ok(), "{:?}", results[0]);
+    assert!(matches!(results[1], Err(imgbb::Error::InvalidParameters { .. })), "{:?}", results[1]);
+
+    let remaining: Vec<_> = ledger.entries().unwrap().into_iter().map(|entry| entry.id).collect();
+    assert_eq!(remaining, ["no_url"]);
+
+    delete.assert_async().await;
+    std::fs::remove_file(ledger.path()).unwrap();
+}
+
+#[tokio::test]
+async fn test_delete_recorded_evicts_cached_responses() {
+    let mut server = mockito::Server::new_async().await;
+    let body = SUCCESS_BODY.replace(
+        "https://ibb.co/2ndCYJK/670a7e48ddcb85ac340c717a41047e5c",
+        &format!("{}/abc/def", server.url()),
+    );
+    let upload = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(body)
+        .expect(2)
+        .create_async()
+        .await;
+    let delete = server
+        .mock("DELETE", "/abc/def")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let ledger = std::sync::Arc::new(imgbb::ledger::JsonLinesLedger::open(
+        std::env::temp_dir().join("imgbb_mock_delete_recorded_dedupe.jsonl"),
+    ));
+    let _ = std::fs::remove_file(ledger.path());
+    let cache = std::sync::Arc::new(imgbb::dedupe::MemoryCache::new());
+
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .dedupe_cache(cache.clone())
+        .ledger(ledger.clone())
+        .build()
+        .unwrap();
+
+    imgbb.upload_builder().bytes(b"image").upload().await.unwrap();
+    imgbb.upload_builder().bytes(b"image").upload().await.unwrap();
+    assert_eq!(cache.len(), 1);
+
+    let results = imgbb.delete_recorded(ledger.entries().unwrap()).await;
+    assert!(results[0].is_ok(), "{:?}", results[0]);
+    assert!(cache.is_empty());
+
+    // The deleted image is uploaded again instead of returning its dead URL
+    imgbb.upload_builder().bytes(b"image").upload().await.unwrap();
+    assert_eq!(ledger.entries().unwrap().len(), 1);
+
+    upload.assert_async().await;
+    delete.assert_async().await;
+    std::fs::remove_file(ledger.path()).unwrap();
+}
+
+#[tokio::test]
+async fn test_ledger_failures_keep_the_response() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(2)
+        .create_async()
+        .await;
+
+    // The ledger file cannot be created, as its directory does not exist
+    let path = std::env::temp_dir().join("imgbb_mock_missing_dir").join("ledger.jsonl");
+    let imgbb = ImgBB::builder("test_key")
+        .base_url(server.url())
+        .ledger(imgbb::ledger::JsonLinesLedger::open(path))
+        .build()
+        .unwrap();
+
+    for result in [
+        imgbb.upload_builder().bytes(b"image").upload().await.map(|_| ()),
+        imgbb.upload_bytes(b"image").await.map(|_| ()),
+    ] {
+        match result {
+            Err(imgbb::Error::Ledger { response, source }) => {
+                let delete_url = response.data.unwrap().delete_url;
+                assert_eq!(
+                    delete_url.as_deref(),
+                    Some("https://ibb.co/2ndCYJK/670a7e48ddcb85ac340c717a41047e5c")
+                );
+                assert!(matches!(*source, imgbb::Error::IOError(_)), "{:?}", source);
+            }
+            other => panic!("expected a ledger error, got {:?}", other),
+        }
+    }
+
+    mock.assert_async().await;
+}
+
+/// Ledger entry of an image uploaded `age` seconds ago
+fn sweep_entry(id: &str, url: String, age: u64, expiration: Option<u64>) -> imgbb::LedgerEntry {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    imgbb::LedgerEntry {
+        id: id.to_string(),
+        url: Some(format!("{}/image.png", url)),
+        url_viewer: None,
+        display_url: None,
+        delete_url: Some(format!("{}/delete", url)),
+        hash: None,
+        source_path: None,
+        name: None,
+        title: None,
+        album: None,
+        time: now - age,
+        expiration,
+        status: imgbb::EntryStatus::Active,
+    }
+}
+
+#[tokio::test]
+async fn test_sweep_marks_expired_and_missing_images() {
+    let mut server = mockito::Server::new_async().await;
+    let mut head = |path: &str, status: usize| {
+        server
+            .mock("HEAD", path)
+            .with_status(status)
+            .expect(1)
+            .create()
+    };
+    let live = head("/live/image.png", 200);
+    let gone = head("/gone/image.png", 404);
+    let failing = head("/failing/image.png", 403);
+
+    let ledger = std::sync::Arc::new(imgbb::ledger::JsonLinesLedger::open(
+        std::env::temp_dir().join("imgbb_mock_sweep_check.jsonl"),
+    ));
+    let _ = std::fs::remove_file(ledger.path());
+
+    let url = |id: &str| format!("{}/{}", server.url(), id);
+    ledger.record(&sweep_entry("expired", url("expired"), 3600, Some(600))).unwrap();
+    ledger.record(&sweep_entry("live", url("live"), 60, Some(600))).unwrap();
+    ledger.record(&sweep_entry("gone", url("gone"), 60, None)).unwrap();
+    ledger.record(&sweep_entry("failing", url("failing"), 60, None)).unwrap();
+
+    let imgbb = ImgBB::builder("test_key").ledger(ledger.clone()).build().unwrap();
+    let policy = imgbb::SweepPolicy::new().check_remote(true);
+
+    let report = imgbb.sweep(&policy).await.unwrap();
+    assert!(report.has_changes());
+    assert_eq!(report.expired[0].id, "expired");
+    assert_eq!(report.missing[0].id, "gone");
+    assert!(report.deleted.is_empty());
+    assert_eq!(report.verified, 1);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].0.id, "failing");
+    assert_eq!(report.failures[0].1.status(), Some(403));
+
+    let statuses: Vec<_> = ledger
+        .entries()
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.id, entry.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("expired".to_string(), imgbb::EntryStatus::Expired),
+            ("live".to_string(), imgbb::EntryStatus::Active),
+            ("gone".to_string(), imgbb::EntryStatus::Missing),
+            ("failing".to_string(), imgbb::EntryStatus::Active),
+        ]
+    );
+
+    // Marked entries are not swept again
+    let report = imgbb.sweep(&imgbb::SweepPolicy::new()).await.unwrap();
+    assert!(!report.has_changes());
+
+    live.assert_async().await;
+    gone.assert_async().await;
+    failing.assert_async().await;
+    std::fs::remove_file(ledger.path()).unwrap();
+}
+
+#[tokio::test]
+async fn test_sweep_deletes_images_past_retention() {
+    let mut server = mockito::Server::new_async().await;
+    let delete = server
+        .mock("DELETE", "/old/delete")
+        .match_query(Matcher::UrlEncoded("key".into(), "test_key".into()))
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let ledger = std::sync::Arc::new(imgbb::ledger::JsonLinesLedger::open(
+        std::env::temp_dir().join("imgbb_mock_sweep_retention.jsonl"),
+    ));
+    let _ = std::fs::remove_file(ledger.path());
+
+    let url = |id: &str| format!("{}/{}", server.url(), id);
+    ledger.record(&sweep_entry("old", url("old"), 2 * 86400, None)).unwrap();
+    ledger.record(&sweep_entry("recent", url("recent"), 60, None)).unwrap();
+
+    let imgbb = ImgBB::builder("test_key").ledger(ledger.clone()).build().unwrap();
+    let policy = imgbb::SweepPolicy::new().retention(Duration::from_secs(86400));
+
+    let report = imgbb.sweep(&policy).await.unwrap();
+    assert_eq!(report.deleted[0].id, "old");
+    assert_eq!(report.verified, 0);
+    assert!(report.failures.is_empty());
+
+    let remaining: Vec<_> = ledger.entries().unwrap().into_iter().map(|entry| entry.id).collect();
+    assert_eq!(remaining, ["recent"]);
+
+    // Sweeping requires a ledger
+    let result = ImgBB::new("test_key").sweep(&policy).await;
+    assert!(matches!(result, Err(imgbb::Error::InvalidParameters { .. })), "{:?}", result);
+
+    delete.assert_async().await;
+    std::fs::remove_file(ledger.path()).unwrap();
+}
+
+#[tokio::test]
+async fn test_sweep_evicts_deleted_and_missing_images_from_dedupe_cache() {
+    let mut server = mockito::Server::new_async().await;
+    let delete = server
+        .mock("DELETE", "/old/delete")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let gone = server.mock("HEAD", "/gone/image.png").with_status(404).expect(1).create();
+    let live = server.mock("HEAD", "/live/image.png").with_status(200).expect(1).create();
+
+    let ledger = std::sync::Arc::new(imgbb::ledger::JsonLinesLedger::open(
+        std::env::temp_dir().join("imgbb_mock_sweep_dedupe.jsonl"),
+    ));
+    let _ = std::fs::remove_file(ledger.path());
+    let cache = std::sync::Arc::new(imgbb::dedupe::MemoryCache::new());
+    let response: imgbb::model::Response = serde_json::from_str(SUCCESS_BODY).unwrap();
+
+    for (id, age) in [("old", 2 * 86400), ("gone", 60), ("live", 60)] {
+        let hash = imgbb::dedupe::ContentHash::of(id.as_bytes());
+        let mut entry = sweep_entry(id, format!("{}/{}", server.url(), id), age, None);
+        entry.hash = Some(hash);
+        ledger.record(&entry).unwrap();
+        cache.insert(hash, &response).unwrap();
+    }
+
+    let imgbb = ImgBB::builder("test_key")
+        .ledger(ledger.clone())
+        .dedupe_cache(cache.clone())
+        .build()
+        .unwrap();
+    let policy = imgbb::SweepPolicy::new()
+        .retention(Duration::from_secs(86400))
+        .check_remote(true);
+
+    let report = imgbb.sweep(&policy).await.unwrap();
+    assert_eq!(report.deleted[0].id, "old");
+    assert_eq!(report.missing[0].id, "gone");
+    assert_eq!(report.verified, 1);
+
+    // Only the image still on ImgBB is answered from the cache
+    assert_eq!(cache.len(), 1);
+    let live_hash = imgbb::dedupe::ContentHash::of(b"live");
+    assert!(cache.get(&live_hash).unwrap().is_some());
+
+    delete.assert_async().await;
+    gone.assert_async().await;
+    live.assert_async().await;
+    std::fs::remove_file(ledger.path()).unwrap();
+}
+
+/// Ledger kept in memory, counting removals and optionally refusing writes
+#[derive(Debug, Default)]
+struct MemoryLedger {
+    entries: std::sync::Mutex<Vec<imgbb::LedgerEntry>>,
+    removals: std::sync::atomic::AtomicUsize,
+    read_only: bool,
+}
+
+impl imgbb::UploadLedger for MemoryLedger {
+    fn record(&self, entry: &imgbb::LedgerEntry) -> Result<(), imgbb::Error> {
+        if self.read_only {
+            return Err(denied());
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|existing| existing.id != entry.id);
+        entries.push(entry.clone());
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<imgbb::LedgerEntry>, imgbb::Error> {
+        Ok(self.entries.lock().unwrap().clone())
+    }
+
+    fn remove(&self, ids: &[&str]) -> Result<usize, imgbb::Error> {
+        self.removals.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if self.read_only {
+            return Err(denied());
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let len = entries.len();
+        entries.retain(|entry| !ids.contains(&entry.id.as_str()));
+        Ok(len - entries.len())
+    }
+}
+
+#[tokio::test]
+async fn test_sweep_removes_deleted_entries_in_one_batch() {
+    let mut server = mockito::Server::new_async().await;
+    let delete = server
+        .mock("DELETE", Matcher::Regex("^/old[12]/delete$".to_string()))
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let url = |id: &str| format!("{}/{}", server.url(), id);
+    let ledger = std::sync::Arc::new(MemoryLedger {
+        entries: std::sync::Mutex::new(vec![
+            sweep_entry("old1", url("old1"), 2 * 86400, None),
+            sweep_entry("old2", url("old2"), 2 * 86400, None),
+            sweep_entry("recent", url("recent"), 60, None),
+        ]),
+        ..MemoryLedger::default()
+    });
+
+    let imgbb = ImgBB::builder("test_key").ledger(ledger.clone()).build().unwrap();
+    let policy = imgbb::SweepPolicy::new().retention(Duration::from_secs(86400));
+
+    let report = imgbb.sweep(&policy).await.unwrap();
+    assert_eq!(report.deleted.len(), 2);
+    assert!(report.failures.is_empty());
+    assert_eq!(ledger.removals.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    let remaining: Vec<_> = ledger.entries().unwrap().into_iter().map(|entry| entry.id).collect();
+    assert_eq!(remaining, ["recent"]);
+
+    delete.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_sweep_reports_ledger_failures() {
+    let mut server = mockito::Server::new_async().await;
+    let delete = server
+        .mock("DELETE", "/old/delete")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(r#"{"success":true}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let url = |id: &str| format!("{}/{}", server.url(), id);
+    let ledger = MemoryLedger {
+        entries: std::sync::Mutex::new(vec![
+            sweep_entry("old", url("old"), 2 * 86400, None),
+            sweep_entry("expired", url("expired"), 3600, Some(600)),
+        ]),
+        read_only: true,
+        ..MemoryLedger::default()
+    };
+
+    let imgbb = ImgBB::builder("test_key").ledger(ledger).build().unwrap();
+    let policy = imgbb::SweepPolicy::new().retention(Duration::from_secs(86400));
+
+    // The report is returned although the ledger cannot be written
+    let report = imgbb.sweep(&policy).await.unwrap();
+    assert_eq!(report.deleted[0].id, "old");
+    assert!(report.expired.is_empty());
+
+    let failures: Vec<_> = report.failures.iter().map(|(entry, _)| entry.id.as_str()).collect();
+    assert_eq!(failures, ["expired", "old"]);
+    assert!(report
+        .failures
+        .iter()
+        .all(|(_, error)| matches!(error, imgbb::Error::IOError(_))));
+
+    delete.assert_async().await;
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_sweep_marks_expired_images() {
+    let ledger = std::sync::Arc::new(imgbb::ledger::JsonLinesLedger::open(
+        std::env::temp_dir().join("imgbb_mock_blocking_sweep.jsonl"),
+    ));
+    let _ = std::fs::remove_file(ledger.path());
+
+    let url = "https://i.ibb.co/abc".to_string();
+    ledger.record(&sweep_entry("expired", url.clone(), 3600, Some(600))).unwrap();
+    ledger.record(&sweep_entry("live", url, 60, Some(600))).unwrap();
+
+    let imgbb = imgbb::blocking::ImgBB::builder("test_key")
+        .ledger(ledger.clone())
+        .build()
+        .unwrap();
+
+    let report = imgbb.sweep(&imgbb::SweepPolicy::new()).unwrap();
+    assert_eq!(report.expired.len(), 1);
+    assert_eq!(report.expired[0].status, imgbb::EntryStatus::Expired);
+    assert_eq!(ledger.find_by_id("live").unwrap().unwrap().status, imgbb::EntryStatus::Active);
+
+    std::fs::remove_file(ledger.path()).unwrap();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_upload_builder() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("key".into(), "test_key".into()),
+            Matcher::UrlEncoded("expiration".into(), "600".into()),
+        ]))
+        .match_body(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("image".into(), "aW1hZ2U=".into()),
+            Matcher::UrlEncoded("title".into(), "Blocking".into()),
+        ]))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create();
+
+    let imgbb = imgbb::blocking::ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let response = imgbb
+        .upload_builder()
+        .bytes(b"image")
+        .title("Blocking")
+        .expiration(600)
+        .upload()
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(response.id, "2ndCYJK");
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_upload_api_error() {
+    let mut server = mockito::Server::new();
+    server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(400)
+        .with_body(r#"{"status_code":400,"error":{"message":"Invalid API v1 key.","code":100},"status_txt":"Bad Request"}"#)
+        .create();
+
+    let imgbb = imgbb::blocking::ImgBB::builder("bad_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let result = imgbb.upload_bytes(b"image");
+    assert!(matches!(result, Err(imgbb::Error::InvalidApiKey { .. })));
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_delete() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("DELETE", "/2ndCYJK/670a7e48")
+        .match_query(Matcher::UrlEncoded("key".into(), "test_key".into()))
+        .with_status(200)
+        .with_body(r#"{"success":true,"status":200}"#)
+        .create();
+
+    let imgbb = imgbb::blocking::ImgBB::new("test_key");
+    imgbb
+        .delete(format!("{}/2ndCYJK/670a7e48", server.url()))
+        .unwrap();
+
+    mock.assert();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_multipart_transport() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .match_header("content-type", Matcher::Regex("^multipart/form-data".into()))
+        .match_body(Matcher::Regex("raw-image-bytes".into()))
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .create();
+
+    let imgbb = imgbb::blocking::ImgBB::builder("test_key")
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    imgbb
+        .upload_builder()
+        .bytes(b"raw-image-bytes")
+        .transport(Transport::Multipart)
+        .upload()
+        .unwrap();
+
+    mock.assert();
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_retry() {
+    let mut server = mockito::Server::new();
+    let failing = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(502)
+        .with_body("Bad Gateway")
+        .expect(1)
+        .create();
+    let success = server
+        .mock("POST", "/upload")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(SUCCESS_BODY)
+        .expect(1)
+        .create();
+
+    let imgbb = imgbb::blocking::ImgBB::builder("test_key")
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    imgbb.upload_bytes(b"image").unwrap();
+
+    failing.assert();
+    success.assert();
+}
@@ -0,0 +1,274 @@
+use imgbb::model::Response;
+use imgbb::{ApiErrorCode, EmbedFormat, ImageVariant, UploadedImage};
+
+// Tests that parse recorded API payloads from tests/fixtures
+
+fn fixture(name: &str) -> Response {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let body = std::fs::read_to_string(&path).unwrap();
+    serde_json::from_str(&body).unwrap_or_else(|e| panic!("{} failed to parse: {}", name, e))
+}
+
+#[test]
+fn test_every_fixture_parses() {
+    let dir = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
+    let mut count = 0;
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        if name.ends_with(".json") {
+            fixture(&name);
+            count += 1;
+        }
+    }
+
+    assert!(count > 0);
+}
+
+#[test]
+fn test_documented_response_with_string_numbers() {
+    let response = fixture("upload_documented.json");
+    assert_eq!(response.success, Some(true));
+    assert_eq!(response.status, Some(200));
+
+    let data = response.data.clone().unwrap();
+    assert_eq!(data.width, Some(1));
+    assert_eq!(data.height, Some(1));
+    assert_eq!(data.size, Some(42));
+    assert_eq!(data.time, Some(1552042565));
+    assert_eq!(data.expiration, Some(0));
+    assert_eq!(data.thumb.unwrap().url.as_deref(), Some("https://i.ibb.co/2ndCYJK/c1f64245afb2.gif"));
+
+    let image = UploadedImage::try_from(response).unwrap();
+    assert_eq!(image.id, "2ndCYJK");
+    assert_eq!(image.size, 42);
+}
+
+#[test]
+fn test_numeric_response() {
+    let image = UploadedImage::try_from(fixture("upload_numeric.json")).unwrap();
+    assert_eq!((image.width, image.height), (1920, 1080));
+    assert_eq!(image.size, 482113);
+    assert_eq!(image.time, Some(1717171717));
+    assert!(image.medium.is_none());
+}
+
+#[test]
+fn test_dimensions_and_sizes_beyond_16_and_32_bits() {
+    let response = fixture("upload_panorama.json");
+    assert_eq!(response.status, Some(200));
+
+    let image = UploadedImage::try_from(response).unwrap();
+    assert_eq!(image.width, 120000);
+    assert_eq!(image.height, 4000);
+    assert_eq!(image.size, 5368709120);
+    assert_eq!(image.expiration, Some(15552000));
+}
+
+#[test]
+fn test_numeric_names_padded_numbers_and_empty_strings() {
+    let image = UploadedImage::try_from(fixture("upload_numeric_names.json")).unwrap();
+    assert_eq!(image.title.as_deref(), Some("20240101"));
+    assert_eq!(image.image.unwrap().name.as_deref(), Some("20240101"));
+    assert_eq!(image.width, 640);
+    assert_eq!(image.expiration, None);
+}
+
+#[test]
+fn test_error_responses() {
+    let error = fixture("error_invalid_key.json").error.unwrap();
+    assert_eq!(error.code, Some(ApiErrorCode::InvalidApiKey));
+    assert_eq!(error.message.as_deref(), Some("Invalid API v1 key."));
+
+    let error = fixture("error_string_code.json").error.unwrap();
+    assert_eq!(error.code, Some(ApiErrorCode::InvalidBase64));
+}
+
+#[test]
+fn test_success_object_is_read_as_true() {
+    let response = fixture("delete_success.json");
+    assert_eq!(response.success, Some(true));
+    assert!(response.data.is_none());
+}
+
+#[test]
+fn test_invalid_numeric_string_is_rejected() {
+    let result = serde_json::from_str::<Response>(r#"{"data":{"width":"wide"}}"#);
+    assert!(result.unwrap_err().to_string().contains("invalid number 'wide'"));
+}
+
+#[test]
+fn test_fixtures_round_trip_through_json() {
+    let names = [
+        "upload_documented.json",
+        "upload_panorama.json",
+        "upload_numeric_names.json",
+        "error_string_code.json",
+        "delete_success.json",
+    ];
+
+    for name in names {
+        let json = serde_json::to_value(fixture(name)).unwrap();
+        let stored: Response = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(stored).unwrap(), json, "{} did not round trip", name);
+    }
+}
+
+#[test]
+fn test_serialized_form_is_canonical() {
+    let json = serde_json::to_value(fixture("upload_panorama.json")).unwrap();
+    assert_eq!(json["status"], 200);
+    assert_eq!(json["data"]["width"], 120000);
+    assert_eq!(json["data"]["size"], 5368709120u64);
+
+    let json = serde_json::to_value(fixture("error_string_code.json")).unwrap();
+    assert_eq!(json["error"]["code"], 120);
+
+    let json = serde_json::to_value(fixture("delete_success.json")).unwrap();
+    assert_eq!(json["success"], true);
+}
+
+#[test]
+fn test_uploaded_image_round_trip() {
+    let image = UploadedImage::try_from(fixture("upload_documented.json")).unwrap();
+    let json = serde_json::to_string(&image).unwrap();
+
+    let stored: UploadedImage = serde_json::from_str(&json).unwrap();
+    assert_eq!(stored.id, image.id);
+    assert_eq!(stored.delete_url, image.delete_url);
+    assert_eq!(serde_json::to_string(&stored).unwrap(), json);
+
+    let data: imgbb::model::Data = serde_json::from_str(&json).unwrap();
+    assert_eq!(data.size, Some(42));
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_json_schema() {
+    let schema = serde_json::to_value(schemars::schema_for!(Response)).unwrap();
+    let data = &schema["$defs"]["Data"]["properties"];
+    assert!(data["width"]["type"].to_string().contains("integer"));
+    assert!(data["size"]["type"].to_string().contains("integer"));
+
+    let error = &schema["$defs"]["ErrorResponse"]["properties"];
+    assert!(error["code"]["type"].to_string().contains("integer"));
+}
+
+#[test]
+fn test_lifetime_accessors() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let image = UploadedImage::try_from(fixture("upload_panorama.json")).unwrap();
+    let uploaded_at = UNIX_EPOCH + Duration::from_secs(1717171717);
+    let expires_at = uploaded_at + Duration::from_secs(15552000);
+
+    assert_eq!(image.uploaded_at(), Some(uploaded_at));
+    assert_eq!(image.lifetime(), Some(Duration::from_secs(15552000)));
+    assert_eq!(image.expires_at(), Some(expires_at));
+    assert!(!image.is_expired(uploaded_at));
+    assert!(image.is_expired(expires_at));
+    assert_eq!(image.remaining_lifetime(expires_at - Duration::from_secs(60)), Some(Duration::from_secs(60)));
+    assert_eq!(image.remaining_lifetime(expires_at + Duration::from_secs(60)), Some(Duration::ZERO));
+
+    // An expiration of zero means the image is kept forever
+    let data = fixture("upload_numeric.json").data.unwrap();
+    assert!(data.uploaded_at().is_some());
+    assert_eq!(data.lifetime(), None);
+    assert_eq!(data.expires_at(), None);
+    assert!(!data.is_expired(UNIX_EPOCH + Duration::from_secs(u32::MAX as u64)));
+    assert_eq!(data.remaining_lifetime(uploaded_at), None);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_lifetime_as_chrono() {
+    let image = UploadedImage::try_from(fixture("upload_panorama.json")).unwrap();
+    assert_eq!(image.uploaded_at_chrono().unwrap().timestamp(), 1717171717);
+    assert_eq!(image.expires_at_chrono().unwrap().timestamp(), 1717171717 + 15552000);
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_lifetime_as_time() {
+    let image = UploadedImage::try_from(fixture("upload_panorama.json")).unwrap();
+    assert_eq!(image.uploaded_at_time().unwrap().unix_timestamp(), 1717171717);
+    assert_eq!(image.expires_at_time().unwrap().unix_timestamp(), 1717171717 + 15552000);
+}
+
+#[test]
+fn test_embed_snippets() {
+    let image = UploadedImage::try_from(fixture("upload_documented.json")).unwrap();
+
+    assert_eq!(
+        image.embed(EmbedFormat::Html).to_string(),
+        r#"<img src="https://i.ibb.co/w04Prt6/c1f64245afb2.gif" alt="c1f64245afb2">"#
+    );
+    assert_eq!(
+        image.embed(EmbedFormat::Html).variant(ImageVariant::Medium).linked(true).to_string(),
+        r#"<a href="https://ibb.co/2ndCYJK"><img src="https://i.ibb.co/98W13PY/c1f64245afb2.gif" alt="c1f64245afb2"></a>"#
+    );
+    assert_eq!(
+        image.embed(EmbedFormat::Markdown).variant(ImageVariant::Thumb).to_string(),
+        "![c1f64245afb2](https://i.ibb.co/2ndCYJK/c1f64245afb2.gif)"
+    );
+    assert_eq!(
+        image.embed(EmbedFormat::Markdown).linked(true).to_string(),
+        "[![c1f64245afb2](https://i.ibb.co/w04Prt6/c1f64245afb2.gif)](https://ibb.co/2ndCYJK)"
+    );
+    assert_eq!(
+        image.embed(EmbedFormat::BBCode).linked(true).to_string(),
+        "[url=https://ibb.co/2ndCYJK][img]https://i.ibb.co/w04Prt6/c1f64245afb2.gif[/img][/url]"
+    );
+    assert_eq!(
+        image.embed(EmbedFormat::ReStructuredText).linked(true).to_string(),
+        ".. image:: https://i.ibb.co/w04Prt6/c1f64245afb2.gif\n   :alt: c1f64245afb2\n   :target: https://ibb.co/2ndCYJK"
+    );
+}
+
+#[test]
+fn test_embed_escapes_text_and_urls() {
+    let image = UploadedImage::try_from(fixture("upload_numeric.json")).unwrap();
+    let alt = "Tom & \"Jerry\" <3 [*draft*]\nsecond line";
+
+    let html = image.embed(EmbedFormat::Html).alt(alt).title("it's").to_string();
+    assert!(html.contains(r#"alt="Tom &amp; &quot;Jerry&quot; &lt;3 [*draft*] second line""#), "{}", html);
+    assert!(html.contains(r#"title="it&#39;s""#), "{}", html);
+
+    let markdown = image.embed(EmbedFormat::Markdown).alt(alt).title("a \"b\"").to_string();
+    assert!(markdown.starts_with(r#"![Tom & "Jerry" \<3 \[\*draft\*\] second line]("#), "{}", markdown);
+    assert!(markdown.ends_with(r#" "a \"b\"")"#), "{}", markdown);
+
+    let rst = image.embed(EmbedFormat::ReStructuredText).alt(alt).to_string();
+    assert_eq!(rst.lines().count(), 2, "{}", rst);
+    assert!(rst.ends_with(r#":alt: Tom & "Jerry" <3 [\*draft\*] second line"#), "{}", rst);
+
+    let rst = image.embed(EmbedFormat::ReStructuredText).alt("|sub| `code` name_ C:\\").to_string();
+    assert!(rst.ends_with(r#":alt: \|sub\| \`code\` name\_ C:\\"#), "{}", rst);
+
+    let bbcode = image.embed(EmbedFormat::BBCode).alt(alt).to_string();
+    assert!(!bbcode.contains("Tom"), "{}", bbcode);
+
+    let data: Response = serde_json::from_str(
+        r#"{"data":{"id":"x","url":"https://i.ibb.co/x/a (1)[2].png"}}"#,
+    )
+    .unwrap();
+    let data = data.data.unwrap();
+    assert_eq!(
+        data.embed(EmbedFormat::Markdown).unwrap().to_string(),
+        "![x](https://i.ibb.co/x/a%20%281%29[2].png)"
+    );
+    assert_eq!(
+        data.embed(EmbedFormat::BBCode).unwrap().to_string(),
+        "[img]https://i.ibb.co/x/a%20(1)%5B2%5D.png[/img]"
+    );
+}
+
+#[test]
+fn test_embed_falls_back_to_full_size() {
+    let image = UploadedImage::try_from(fixture("upload_numeric.json")).unwrap();
+    let embed = image.embed(EmbedFormat::Html).variant(ImageVariant::Medium);
+    assert_eq!(embed.src(), image.url);
+
+    let data: Response = serde_json::from_str(r#"{"data":{"id":"x"}}"#).unwrap();
+    assert!(data.data.unwrap().embed(EmbedFormat::Html).is_none());
+}